@@ -3,27 +3,26 @@ use crate::routes;
 use crate::watcher;
 use log::{debug, error, info, warn};
 
-/// Initialize the blog store
+/// Initialize the blog store, returning the post files that failed to parse
 pub async fn init_blog_store(
     content_dir: &std::path::PathBuf,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<Vec<std::path::PathBuf>, Box<dyn std::error::Error>> {
     info!("Initializing blog store from {content_dir:?}");
 
     let blog_store = routes::get_blog_store();
     debug!("Blog store instance retrieved");
 
     match blog_store.load_posts().await {
-        Ok(_) => {
-            let post_count = blog_store.get_all_posts().len();
+        Ok(failed) => {
+            let post_count = blog_store.post_count();
             info!("Successfully loaded {post_count} blog posts");
+            Ok(failed)
         }
         Err(e) => {
             error!("Failed to load posts: {e}");
-            return Err(format!("Failed to load posts: {e}").into());
+            Err(format!("Failed to load posts: {e}").into())
         }
     }
-
-    Ok(())
 }
 
 /// Initialize the application
@@ -39,17 +38,25 @@ pub async fn init_app(config: &Config) -> Result<(), Box<dyn std::error::Error>>
     // Initialize blog store
     init_blog_store(&config.content_dir).await?;
 
+    // Load templates before serving so the watcher always has a good set to fall back to
+    if let Err(e) = routes::init_template_engine() {
+        error!("Failed to load templates: {e}");
+        return Err(format!("Failed to load templates: {e}").into());
+    }
+
+    if let Err(e) = routes::init_assets() {
+        error!("Failed to fingerprint static assets: {e}");
+        return Err(format!("Failed to fingerprint static assets: {e}").into());
+    }
+
     // Setup file watching for hot reload
-    if config.content_dir_exists() {
-        info!("Content directory exists, enabling hot reload");
-        watcher::setup_file_watcher(config.content_dir.clone()).await?;
-        info!("File watching enabled for hot reload");
-    } else {
+    if !config.content_dir_exists() {
         warn!(
-            "Content directory does not exist: {:?}, hot reload disabled",
+            "Content directory does not exist: {:?}, post reload disabled",
             config.content_dir
         );
     }
+    watcher::setup_file_watcher(config).await?;
 
     info!("Application initialization completed successfully");
     Ok(())
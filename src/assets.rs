@@ -0,0 +1,259 @@
+//! Fingerprinted static assets
+//!
+//! At startup every file under the static directory is hashed into an [`AssetManifest`]
+//! that maps its logical path (`css/style.css`) to a URL with the hash in the file name
+//! (`/static/css/style.0123456789abcdef.css`). Templates link assets with
+//! `{{ asset "css/style.css" }}`, so a changed file always gets a new URL and browsers
+//! can cache every URL forever.
+//!
+//! Stylesheets go through the [`css`](crate::css) pipeline first and are served from
+//! memory; their fingerprint is the hash of the bundle, so editing an imported file
+//! changes the URL of every stylesheet that imports it.
+
+use crate::css;
+use crate::hash::fnv1a;
+use crate::routes;
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::{HeaderValue, Uri, header};
+use axum::middleware::Next;
+use axum::response::Response;
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// URL prefix the static directory is served under
+pub const STATIC_PREFIX: &str = "/static";
+
+/// Cache header for URLs whose content can never change
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Hex digits of the fingerprint in a file name
+const FINGERPRINT_LEN: usize = 16;
+
+/// Logical asset paths mapped to their content hashes
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AssetManifest {
+    hashes: HashMap<String, String>,
+    /// Bundled stylesheets, served instead of the files on disk
+    stylesheets: HashMap<String, Arc<str>>,
+}
+
+impl AssetManifest {
+    /// Hash every file under `static_dir`, bundling stylesheets and minifying them if
+    /// `minified` is set
+    ///
+    /// A missing directory gives an empty manifest.
+    pub fn build(
+        static_dir: &Path,
+        minified: bool,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut manifest = Self::default();
+        if !static_dir.is_dir() {
+            warn!("Static directory {static_dir:?} does not exist, no assets to fingerprint");
+            return Ok(manifest);
+        }
+
+        let mut dirs = vec![static_dir.to_path_buf()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                let logical = path
+                    .strip_prefix(static_dir)?
+                    .components()
+                    .map(|part| part.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                let hash = if path.extension().is_some_and(|ext| ext == "css") {
+                    let bundle = css::bundle(static_dir, &logical, minified)?;
+                    let hash = fnv1a(bundle.as_bytes());
+                    manifest.stylesheets.insert(logical.clone(), bundle.into());
+                    hash
+                } else {
+                    let bytes = fs::read(&path)
+                        .map_err(|e| format!("Failed to read asset {path:?}: {e}"))?;
+                    fnv1a(&bytes)
+                };
+                manifest.hashes.insert(logical, format!("{hash:016x}"));
+            }
+        }
+        debug!(
+            "Fingerprinted {} static assets, {} of them stylesheets",
+            manifest.hashes.len(),
+            manifest.stylesheets.len()
+        );
+        Ok(manifest)
+    }
+
+    /// Fingerprinted URL of an asset
+    ///
+    /// Unknown assets get their plain URL, so a typo shows up as a 404 rather than a broken page.
+    pub fn url(&self, logical: &str) -> String {
+        let logical = logical.trim_start_matches('/');
+        match self.hashes.get(logical) {
+            Some(hash) => format!("{STATIC_PREFIX}/{}", fingerprinted_path(logical, hash)),
+            None => {
+                warn!("Asset not found in {STATIC_PREFIX}: {logical}");
+                format!("{STATIC_PREFIX}/{logical}")
+            }
+        }
+    }
+
+    /// Content hash of an asset, or `None` if there is no such file
+    pub fn fingerprint(&self, logical: &str) -> Option<&str> {
+        self.hashes
+            .get(logical.trim_start_matches('/'))
+            .map(String::as_str)
+    }
+
+    /// Bundled contents of a stylesheet
+    pub fn stylesheet(&self, logical: &str) -> Option<Arc<str>> {
+        self.stylesheets
+            .get(logical.trim_start_matches('/'))
+            .cloned()
+    }
+
+    /// Every asset as (logical path, fingerprinted path), both relative to the static directory
+    pub fn fingerprinted_paths(&self) -> impl Iterator<Item = (&str, String)> {
+        self.hashes
+            .iter()
+            .map(|(logical, hash)| (logical.as_str(), fingerprinted_path(logical, hash)))
+    }
+
+    /// Resolve a fingerprinted path back to its logical path and whether the hash is current
+    fn resolve(&self, path: &str) -> Option<(String, bool)> {
+        let (dir, file) = match path.rsplit_once('/') {
+            Some((dir, file)) => (format!("{dir}/"), file),
+            None => (String::new(), path),
+        };
+        let parts: Vec<&str> = file.rsplitn(3, '.').collect();
+        let (hash, file) = match parts.as_slice() {
+            // `name.<hash>`, for files without an extension
+            [hash, ..] if is_fingerprint(hash) => {
+                (*hash, file[..file.len() - hash.len() - 1].to_string())
+            }
+            // `name.<hash>.ext`
+            [ext, hash, name] if is_fingerprint(hash) => (*hash, format!("{name}.{ext}")),
+            _ => return None,
+        };
+
+        let logical = format!("{dir}{file}");
+        let current = self.hashes.get(&logical)? == hash;
+        Some((logical, current))
+    }
+}
+
+/// Insert the hash before the extension: `css/style.css` -> `css/style.<hash>.css`
+fn fingerprinted_path(logical: &str, hash: &str) -> String {
+    let (dir, file) = match logical.rsplit_once('/') {
+        Some((dir, file)) => (format!("{dir}/"), file),
+        None => (String::new(), logical),
+    };
+    match file.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{dir}{stem}.{hash}.{ext}"),
+        _ => format!("{dir}{file}.{hash}"),
+    }
+}
+
+fn is_fingerprint(part: &str) -> bool {
+    part.len() == FINGERPRINT_LEN && part.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Middleware for the static files service: serves fingerprinted URLs from the plain file
+/// and stylesheets from their bundles
+///
+/// URLs with the current hash are cached forever. A stale hash still gets the current
+/// file, without the long cache, so pages rendered before a change keep working.
+pub async fn serve_fingerprinted(mut request: Request, next: Next) -> Response {
+    let assets = routes::get_assets();
+    let path = request.uri().path().trim_start_matches('/');
+    let resolved = assets.resolve(path);
+    let (logical, current) = match &resolved {
+        Some((logical, current)) => (logical.as_str(), *current),
+        None => (path, false),
+    };
+    if let Some(css) = assets.stylesheet(logical) {
+        return stylesheet_response(css, current);
+    }
+    let Some((logical, current)) = resolved else {
+        return next.run(request).await;
+    };
+
+    let rewritten = match request.uri().query() {
+        Some(query) => format!("/{logical}?{query}"),
+        None => format!("/{logical}"),
+    };
+    match rewritten.parse::<Uri>() {
+        Ok(uri) => *request.uri_mut() = uri,
+        Err(e) => {
+            warn!("Failed to rewrite asset URL {path}: {e}");
+            return next.run(request).await;
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if current && response.status().is_success() {
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static(IMMUTABLE));
+    }
+    response
+}
+
+fn stylesheet_response(css: Arc<str>, immutable: bool) -> Response {
+    let mut response = Response::new(Body::from(css.to_string()));
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/css; charset=utf-8"),
+    );
+    if immutable {
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(IMMUTABLE));
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprinted_urls_round_trip() {
+        let hash = "0123456789abcdef";
+        let manifest = AssetManifest {
+            hashes: [
+                ("css/style.css", hash),
+                ("favicon.ico", hash),
+                ("LICENSE", hash),
+            ]
+            .into_iter()
+            .map(|(path, hash)| (path.to_string(), hash.to_string()))
+            .collect(),
+            ..AssetManifest::default()
+        };
+
+        assert_eq!(
+            manifest.url("css/style.css"),
+            "/static/css/style.0123456789abcdef.css"
+        );
+        assert_eq!(manifest.url("/LICENSE"), "/static/LICENSE.0123456789abcdef");
+        assert_eq!(manifest.url("css/missing.css"), "/static/css/missing.css");
+
+        for logical in ["css/style.css", "favicon.ico", "LICENSE"] {
+            let path = fingerprinted_path(logical, hash);
+            assert_eq!(manifest.resolve(&path), Some((logical.to_string(), true)));
+        }
+        assert_eq!(
+            manifest.resolve("css/style.fedcba9876543210.css"),
+            Some(("css/style.css".to_string(), false))
+        );
+        assert_eq!(manifest.resolve("css/style.css"), None);
+        assert_eq!(manifest.resolve("css/other.0123456789abcdef.css"), None);
+    }
+}
@@ -1,12 +1,21 @@
+use crate::components::code_block::process_markdown_content;
+use crate::components::custom_components::{
+    preprocess_markdown_with_components, restore_components,
+};
+use crate::components::search::SortOption;
+use crate::routes::get_config;
+use crate::sanitize::Allowlist;
+use crate::templates::Value;
 use chrono::{DateTime, NaiveDateTime, Utc};
 use log::{debug, error, info, warn};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use tokio::fs;
 
 /// Blog post metadata from YAML frontmatter
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BlogPostMeta {
     pub title: String,
     pub excerpt: String,
@@ -14,6 +23,12 @@ pub struct BlogPostMeta {
     pub tags: Vec<String>,
     pub published_at: DateTime<Utc>,
     pub draft: bool,
+    /// Raw HTML in the post is trusted and skips sanitization
+    pub trusted_html: bool,
+    /// Share image for link previews, overriding the site default
+    pub image: Option<String>,
+    /// Original URL of a cross-posted article, used as the canonical link
+    pub canonical_url: Option<String>,
 }
 
 impl BlogPostMeta {
@@ -25,6 +40,7 @@ impl BlogPostMeta {
         tags: Vec<String>,
         published_at: &str,
         draft: bool,
+        trusted_html: bool,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let published_at = Self::parse_date(published_at)?;
 
@@ -35,6 +51,9 @@ impl BlogPostMeta {
             tags,
             published_at,
             draft,
+            trusted_html,
+            image: None,
+            canonical_url: None,
         })
     }
 
@@ -75,7 +94,10 @@ impl BlogPostMeta {
 pub struct BlogPost {
     pub slug: String,
     pub meta: BlogPostMeta,
+    #[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
     pub content: String,
+    /// Markdown content rendered to HTML once at load time
+    pub html: String,
 }
 
 impl BlogPost {
@@ -84,22 +106,31 @@ impl BlogPost {
         file_path: PathBuf,
     ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
         let content = fs::read_to_string(&file_path).await?;
-        let slug = file_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .ok_or("Invalid filename")?
-            .to_string();
+        let slug = slug_from_path(&file_path).ok_or("Invalid filename")?;
 
         // Parse frontmatter and content
         let (meta, content) = Self::parse_frontmatter(&content)?;
+        let html = Self::render_content(&content, meta.trusted_html);
+        debug!("Rendered post {slug}: {} chars of HTML", html.len());
 
         Ok(Self {
             slug,
             meta,
             content,
+            html,
         })
     }
 
+    /// Render markdown content with custom components and enhanced code blocks
+    ///
+    /// Raw HTML is sanitized with the configured allowlist unless the post is trusted.
+    fn render_content(content: &str, trusted_html: bool) -> String {
+        let preprocessed = preprocess_markdown_with_components(content);
+        let allowlist = (!trusted_html).then(|| Allowlist::with_extra(&get_config().allowed_html));
+        let html = process_markdown_content(&preprocessed.markdown, allowlist.as_ref());
+        restore_components(&html, &preprocessed.components)
+    }
+
     /// Parse YAML frontmatter from markdown content (manual parsing, no serde)
     fn parse_frontmatter(
         content: &str,
@@ -123,6 +154,9 @@ impl BlogPost {
         let mut tags = Vec::new();
         let mut published_at = None;
         let mut draft = false;
+        let mut trusted_html = false;
+        let mut image = None;
+        let mut canonical_url = None;
 
         for line in frontmatter.lines() {
             let line = line.trim();
@@ -145,17 +179,30 @@ impl BlogPost {
                 published_at = Some(stripped.trim().trim_matches('"').to_string());
             } else if let Some(stripped) = line.strip_prefix("draft:") {
                 draft = stripped.trim().eq_ignore_ascii_case("true");
+            } else if let Some(stripped) = line.strip_prefix("trusted_html:") {
+                trusted_html = stripped.trim().eq_ignore_ascii_case("true");
+            } else if let Some(stripped) = line.strip_prefix("image:") {
+                image =
+                    Some(stripped.trim().trim_matches('"').to_string()).filter(|s| !s.is_empty());
+            } else if let Some(stripped) = line.strip_prefix("canonical_url:") {
+                canonical_url =
+                    Some(stripped.trim().trim_matches('"').to_string()).filter(|s| !s.is_empty());
             }
         }
 
-        let meta = BlogPostMeta::new(
-            title.ok_or("Missing or invalid title")?,
-            excerpt.ok_or("Missing or invalid excerpt")?,
-            author.ok_or("Missing or invalid author")?,
-            tags,
-            &published_at.ok_or("Missing or invalid published_at")?,
-            draft,
-        )?;
+        let meta = BlogPostMeta {
+            image,
+            canonical_url,
+            ..BlogPostMeta::new(
+                title.ok_or("Missing or invalid title")?,
+                excerpt.ok_or("Missing or invalid excerpt")?,
+                author.ok_or("Missing or invalid author")?,
+                tags,
+                &published_at.ok_or("Missing or invalid published_at")?,
+                draft,
+                trusted_html,
+            )?
+        };
 
         Ok((meta, content.to_string()))
     }
@@ -165,16 +212,6 @@ impl BlogPost {
         self.meta.tags.iter().any(|t| t == tag)
     }
 
-    /// Get formatted publication date
-    pub fn formatted_date(&self) -> String {
-        self.meta.published_at.format("%B %d, %Y").to_string()
-    }
-
-    /// Get ISO date for datetime attribute
-    pub fn iso_date(&self) -> String {
-        self.meta.published_at.format("%Y-%m-%d").to_string()
-    }
-
     /// Get title
     pub fn title(&self) -> &str {
         &self.meta.title
@@ -201,116 +238,575 @@ impl BlogPost {
     }
 }
 
+/// Template view of a post: `slug`, `url`, `title`, `excerpt`, `author`, `tags` (sorted),
+/// `date` and `draft`
+impl From<&BlogPost> for Value {
+    fn from(post: &BlogPost) -> Self {
+        let mut tags = post.tags().to_vec();
+        tags.sort();
+        [
+            ("slug", Value::from(&post.slug)),
+            ("url", Value::from(format!("/blog/{}", post.slug))),
+            ("title", post.title().into()),
+            ("excerpt", post.excerpt().into()),
+            ("author", post.author().into()),
+            ("tags", tags.into()),
+            ("date", post.meta.published_at.into()),
+            ("draft", post.meta.draft.into()),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Immutable view of all published posts with precomputed indices
+///
+/// A snapshot is never mutated after it is built; reloads build a new one and swap it in.
+#[derive(Debug, Default)]
+pub struct BlogSnapshot {
+    posts: HashMap<String, Arc<BlogPost>>,
+    by_date: Vec<Arc<BlogPost>>,
+    by_title: Vec<Arc<BlogPost>>,
+    by_tag: BTreeMap<String, Vec<Arc<BlogPost>>>,
+}
+
+impl BlogSnapshot {
+    /// Build a snapshot and its sorted indices from a set of posts
+    pub fn new(posts: HashMap<String, Arc<BlogPost>>) -> Self {
+        let mut by_date: Vec<Arc<BlogPost>> = posts.values().cloned().collect();
+        by_date.sort_by(|a, b| {
+            b.meta
+                .published_at
+                .cmp(&a.meta.published_at)
+                .then_with(|| a.slug.cmp(&b.slug))
+        });
+
+        let mut by_title = by_date.clone();
+        by_title.sort_by(|a, b| a.title().cmp(b.title()).then_with(|| a.slug.cmp(&b.slug)));
+
+        let mut by_tag: BTreeMap<String, Vec<Arc<BlogPost>>> = BTreeMap::new();
+        for post in &by_date {
+            // A tag listed twice in the frontmatter still lists the post once
+            let mut tags: Vec<&String> = post.tags().iter().collect();
+            tags.sort();
+            tags.dedup();
+            for tag in tags {
+                by_tag
+                    .entry(tag.clone())
+                    .or_default()
+                    .push(Arc::clone(post));
+            }
+        }
+
+        Self {
+            posts,
+            by_date,
+            by_title,
+            by_tag,
+        }
+    }
+
+    /// Get a specific blog post by slug
+    pub fn get(&self, slug: &str) -> Option<&Arc<BlogPost>> {
+        self.posts.get(slug)
+    }
+
+    /// All posts, newest first
+    pub fn posts_by_date(&self) -> &[Arc<BlogPost>] {
+        &self.by_date
+    }
+
+    /// All posts, sorted alphabetically by title
+    pub fn posts_by_title(&self) -> &[Arc<BlogPost>] {
+        &self.by_title
+    }
+
+    /// Posts carrying a tag, newest first
+    pub fn posts_with_tag(&self, tag: &str) -> &[Arc<BlogPost>] {
+        self.by_tag.get(tag).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Posts carrying every tag in `tags`, in `sort` order
+    ///
+    /// Starts from the pre-sorted index for the order, narrowed to the first tag when
+    /// there is one. Relevance has no index of its own and lists the newest first.
+    pub fn list(&self, sort: &SortOption, tags: &[String]) -> Vec<&BlogPost> {
+        let index = match (sort, tags.first()) {
+            (SortOption::Title, _) => self.posts_by_title(),
+            (_, Some(tag)) => self.posts_with_tag(tag),
+            (_, None) => self.posts_by_date(),
+        };
+        let posts = index
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|post: &&BlogPost| tags.iter().all(|tag| post.has_tag(tag)));
+        if *sort == SortOption::DateOldest {
+            posts.rev().collect()
+        } else {
+            posts.collect()
+        }
+    }
+
+    /// All unique tags, sorted
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.by_tag.keys().map(String::as_str)
+    }
+
+    /// Number of posts in the snapshot
+    pub fn len(&self) -> usize {
+        self.posts.len()
+    }
+}
+
+/// Slugs affected by an incremental reload
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+pub struct ReloadDiff {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    /// Posts that failed to parse; their previous version stays online
+    pub failed: Vec<String>,
+}
+
+#[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+impl ReloadDiff {
+    /// Whether the reload changed the set of served posts
+    pub fn has_changes(&self) -> bool {
+        !(self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty())
+    }
+}
+
+impl fmt::Display for ReloadDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sections = [
+            ("added", &self.added),
+            ("updated", &self.updated),
+            ("removed", &self.removed),
+            ("failed", &self.failed),
+        ];
+        let parts: Vec<String> = sections
+            .iter()
+            .filter(|(_, slugs)| !slugs.is_empty())
+            .map(|(label, slugs)| format!("{label}: {}", slugs.join(", ")))
+            .collect();
+
+        if parts.is_empty() {
+            write!(f, "no changes")
+        } else {
+            write!(f, "{}", parts.join("; "))
+        }
+    }
+}
+
 /// Blog storage and management with hot reload
+///
+/// Request handlers only clone the current snapshot `Arc` under a read lock. Reloads
+/// parse and render outside the lock and take the write lock just to swap the pointer.
 pub struct BlogStore {
-    posts: Arc<RwLock<HashMap<String, BlogPost>>>,
+    snapshot: RwLock<Arc<BlogSnapshot>>,
     content_dir: PathBuf,
+    /// Serve draft posts alongside published ones (for previewing)
+    include_drafts: bool,
 }
 
 impl BlogStore {
     /// Create a new blog store
-    pub fn new(content_dir: PathBuf) -> Self {
+    pub fn new(content_dir: PathBuf, include_drafts: bool) -> Self {
         Self {
-            posts: Arc::new(RwLock::new(HashMap::new())),
+            snapshot: RwLock::new(Arc::new(BlogSnapshot::default())),
             content_dir,
+            include_drafts,
         }
     }
 
+    /// Whether a parsed post should be served
+    fn is_visible(&self, post: &BlogPost) -> bool {
+        post.is_published() || self.include_drafts
+    }
+
     /// Load all blog posts from the content directory
-    pub async fn load_posts(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    ///
+    /// Posts that fail to parse are logged and left out; their files are returned.
+    pub async fn load_posts(
+        &self,
+    ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
         info!("Loading blog posts from {:?}", self.content_dir);
 
         let mut posts = HashMap::new();
+        let mut failed = Vec::new();
 
         if !self.content_dir.exists() {
             warn!("Content directory does not exist: {:?}", self.content_dir);
-            return Ok(());
+            return Ok(failed);
+        }
+
+        for scanned in scan_posts(&self.content_dir).await? {
+            match scanned.post {
+                Ok(post) if self.is_visible(&post) => {
+                    let slug = post.slug.clone();
+                    posts.insert(slug.clone(), Arc::new(post));
+                    info!("Loaded blog post: {slug}");
+                }
+                Ok(post) => {
+                    debug!("Skipped draft post: {}", post.slug);
+                }
+                Err(e) => {
+                    error!("Failed to load blog post {:?}: {e}", scanned.path);
+                    failed.push(scanned.path);
+                }
+            }
         }
 
-        debug!("Reading content directory: {:?}", self.content_dir);
-        let mut entries = fs::read_dir(&self.content_dir).await?;
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            debug!("Checking file: {path:?}");
-
-            if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                debug!("Processing markdown file: {path:?}");
-                match BlogPost::from_file(path.clone()).await {
-                    Ok(post) => {
-                        if post.is_published() {
-                            let slug = post.slug.clone();
-                            posts.insert(slug.clone(), post);
-                            info!("Loaded blog post: {slug}");
-                        } else {
-                            debug!("Skipped draft post: {}", post.slug);
-                        }
+        let snapshot = BlogSnapshot::new(posts);
+        let post_count = snapshot.len();
+        self.publish(snapshot);
+
+        info!("Successfully loaded {post_count} blog posts");
+        debug!("Blog store updated with {post_count} posts");
+        Ok(failed)
+    }
+
+    /// Re-parse only the given files and publish a snapshot with the changes applied
+    ///
+    /// Missing files are treated as removals, so renames resolve to a removal of the old
+    /// slug plus an addition of the new one. A file that fails to parse keeps its
+    /// previously loaded version online.
+    #[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+    pub async fn reload_paths(&self, paths: &[PathBuf]) -> ReloadDiff {
+        let current = self.snapshot();
+        let mut posts = current.posts.clone();
+        let mut diff = ReloadDiff::default();
+
+        for path in paths {
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                debug!("Skipping non-markdown path: {path:?}");
+                continue;
+            }
+            let Some(slug) = slug_from_path(path) else {
+                warn!("Skipping path without a valid slug: {path:?}");
+                continue;
+            };
+
+            if !path.exists() {
+                if posts.remove(&slug).is_some() {
+                    diff.removed.push(slug);
+                }
+                continue;
+            }
+
+            match BlogPost::from_file(path.clone()).await {
+                Ok(post) if self.is_visible(&post) => match posts.get(&slug) {
+                    Some(old) if old.meta == post.meta && old.content == post.content => {
+                        debug!("Post unchanged: {slug}");
+                    }
+                    Some(_) => {
+                        posts.insert(slug.clone(), Arc::new(post));
+                        diff.updated.push(slug);
                     }
-                    Err(e) => {
-                        error!("Failed to load blog post {path:?}: {e}");
+                    None => {
+                        posts.insert(slug.clone(), Arc::new(post));
+                        diff.added.push(slug);
                     }
+                },
+                Ok(_) => {
+                    debug!("Post is a draft: {slug}");
+                    if posts.remove(&slug).is_some() {
+                        diff.removed.push(slug);
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to reload blog post {path:?}: {e}");
+                    diff.failed.push(slug);
                 }
-            } else {
-                debug!("Skipping non-markdown file: {path:?}");
             }
         }
 
-        {
-            let mut posts_guard = self.posts.write().unwrap();
-            *posts_guard = posts;
+        if diff.has_changes() {
+            self.publish(BlogSnapshot::new(posts));
         }
 
-        let post_count = self.posts.read().unwrap().len();
-        info!("Successfully loaded {post_count} blog posts");
-        debug!("Blog store updated with {post_count} posts");
-        Ok(())
+        diff
     }
 
-    /// Get all blog posts, sorted by publication date (newest first)
-    pub fn get_all_posts(&self) -> Vec<BlogPost> {
-        let posts_guard = self.posts.read().unwrap();
-        let mut posts: Vec<BlogPost> = posts_guard.values().cloned().collect();
-        posts.sort_by(|a, b| b.meta.published_at.cmp(&a.meta.published_at));
-        posts
+    /// Atomically replace the current snapshot
+    fn publish(&self, snapshot: BlogSnapshot) {
+        let snapshot = Arc::new(snapshot);
+        let mut guard = self.snapshot.write().unwrap();
+        *guard = snapshot;
+    }
+
+    /// Get the current snapshot of all published posts
+    pub fn snapshot(&self) -> Arc<BlogSnapshot> {
+        Arc::clone(&self.snapshot.read().unwrap())
     }
 
     /// Get a specific blog post by slug
-    pub fn get_post_by_slug(&self, slug: &str) -> Option<BlogPost> {
-        let posts_guard = self.posts.read().unwrap();
-        posts_guard.get(slug).cloned()
+    pub fn get_post_by_slug(&self, slug: &str) -> Option<Arc<BlogPost>> {
+        self.snapshot().get(slug).cloned()
     }
 
     /// Get recent blog posts (limit to specified count)
-    pub fn get_recent_posts(&self, limit: usize) -> Vec<BlogPost> {
-        let mut posts = self.get_all_posts();
-        posts.truncate(limit);
-        posts
+    pub fn get_recent_posts(&self, limit: usize) -> Vec<Arc<BlogPost>> {
+        self.snapshot()
+            .posts_by_date()
+            .iter()
+            .take(limit)
+            .cloned()
+            .collect()
     }
 
-    /// Get posts by tag
-    pub fn get_posts_by_tag(&self, tag: &str) -> Vec<BlogPost> {
-        self.get_all_posts()
-            .into_iter()
-            .filter(|post| post.has_tag(tag))
-            .collect()
+    /// Get the number of published posts
+    pub fn post_count(&self) -> usize {
+        self.snapshot().len()
     }
+}
 
-    /// Get all unique tags
-    pub fn get_all_tags(&self) -> Vec<String> {
-        let posts_guard = self.posts.read().unwrap();
-        let mut tags: Vec<String> = posts_guard
-            .values()
-            .flat_map(|post| &post.meta.tags)
-            .cloned()
-            .collect();
-        tags.sort();
-        tags.dedup();
-        tags
+/// A markdown file found in the content directory and the result of parsing it
+pub struct ScannedPost {
+    pub path: PathBuf,
+    pub post: Result<BlogPost, Box<dyn std::error::Error + Send + Sync>>,
+}
+
+/// Parse every markdown file in a directory, drafts included, sorted by path
+pub async fn scan_posts(
+    content_dir: &Path,
+) -> Result<Vec<ScannedPost>, Box<dyn std::error::Error + Send + Sync>> {
+    debug!("Reading content directory: {content_dir:?}");
+    let mut entries = fs::read_dir(content_dir).await?;
+    let mut paths = Vec::new();
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        debug!("Checking file: {path:?}");
+
+        if path.extension().and_then(|s| s.to_str()) == Some("md") {
+            paths.push(path);
+        } else {
+            debug!("Skipping non-markdown file: {path:?}");
+        }
+    }
+    paths.sort();
+
+    let mut scanned = Vec::with_capacity(paths.len());
+    for path in paths {
+        debug!("Processing markdown file: {path:?}");
+        let post = BlogPost::from_file(path.clone()).await;
+        scanned.push(ScannedPost { path, post });
     }
+    Ok(scanned)
+}
+
+/// Derive a post slug from its markdown file name
+fn slug_from_path(path: &Path) -> Option<String> {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .map(str::to_string)
 }
 
 impl Default for BlogStore {
     fn default() -> Self {
-        Self::new(PathBuf::from("content"))
+        Self::new(PathBuf::from("content"), false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::search::SearchComponent;
+    use crate::page_meta::PageMeta;
+    use crate::templates::{Context, TemplateEngine};
+    use std::borrow::Borrow;
+
+    fn write_post(dir: &Path, slug: &str, title: &str) {
+        let post = format!(
+            "---\ntitle: \"{title}\"\nexcerpt: \"Excerpt\"\nauthor: \"Nornity\"\ntags: [\"rust\"]\npublished_at: \"2025-01-01 10:00:00\"\n---\n\nBody of {slug}.\n"
+        );
+        std::fs::write(dir.join(format!("{slug}.md")), post).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_incremental_reload_diff() {
+        let dir = std::env::temp_dir().join(format!("nornity-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_post(&dir, "first", "First");
+        write_post(&dir, "second", "Second");
+
+        let store = BlogStore::new(dir.clone(), false);
+        store.load_posts().await.unwrap();
+        assert_eq!(store.post_count(), 2);
+
+        // Update one post, rename another and add a new one
+        write_post(&dir, "first", "First, revised");
+        std::fs::rename(dir.join("second.md"), dir.join("renamed.md")).unwrap();
+        write_post(&dir, "third", "Third");
+        let diff = store
+            .reload_paths(&[
+                dir.join("first.md"),
+                dir.join("second.md"),
+                dir.join("renamed.md"),
+                dir.join("third.md"),
+                dir.join("notes.txt"),
+            ])
+            .await;
+
+        assert_eq!(diff.updated, vec!["first"]);
+        assert_eq!(diff.removed, vec!["second"]);
+        assert_eq!(diff.added, vec!["renamed", "third"]);
+        assert_eq!(
+            store.get_post_by_slug("first").unwrap().title(),
+            "First, revised"
+        );
+
+        // A broken edit keeps the last good version online
+        std::fs::write(dir.join("third.md"), "no frontmatter").unwrap();
+        let diff = store.reload_paths(&[dir.join("third.md")]).await;
+
+        assert_eq!(diff.failed, vec!["third"]);
+        assert!(!diff.has_changes());
+        assert_eq!(store.get_post_by_slug("third").unwrap().title(), "Third");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_indices() {
+        let post = |slug: &str, title: &str, published_at: &str, tags: &[&str]| {
+            let tags = tags.iter().map(|tag| tag.to_string()).collect();
+            let meta = BlogPostMeta::new(
+                title.to_string(),
+                String::new(),
+                "Ada".to_string(),
+                tags,
+                published_at,
+                false,
+                false,
+            )
+            .unwrap();
+            let post = BlogPost {
+                slug: slug.to_string(),
+                meta,
+                content: String::new(),
+                html: String::new(),
+            };
+            (slug.to_string(), Arc::new(post))
+        };
+        let snapshot = BlogSnapshot::new(HashMap::from([
+            post("b", "Beta", "2025-01-02 10:00:00", &["rust", "web"]),
+            post("a", "Zeta", "2025-01-02 10:00:00", &["rust", "rust"]),
+            post("c", "Alpha", "2025-01-01 10:00:00", &["rust"]),
+            post("d", "Alpha", "2025-01-03 10:00:00", &["web"]),
+        ]));
+        fn slugs<P: Borrow<BlogPost>>(posts: &[P]) -> Vec<&str> {
+            posts
+                .iter()
+                .map(|post| post.borrow().slug.as_str())
+                .collect()
+        }
+
+        // Posts published at the same time are ordered by slug, as are equal titles
+        assert_eq!(slugs(snapshot.posts_by_date()), ["d", "a", "b", "c"]);
+        assert_eq!(slugs(snapshot.posts_by_title()), ["c", "d", "b", "a"]);
+        // A tag repeated in the frontmatter lists the post once
+        assert_eq!(slugs(snapshot.posts_with_tag("rust")), ["a", "b", "c"]);
+        assert_eq!(snapshot.tags().collect::<Vec<_>>(), ["rust", "web"]);
+
+        let search = SearchComponent::parse_params_from_raw_query("sort=date-oldest&tag=rust");
+        let posts = snapshot.list(&search.sort_by, &search.selected_tags);
+        assert_eq!(slugs(&posts), ["c", "b", "a"]);
+        let tags = ["web".to_string(), "rust".to_string()];
+        assert_eq!(slugs(&snapshot.list(&SortOption::Title, &tags)), ["b"]);
+        assert_eq!(
+            slugs(&snapshot.list(&SortOption::DateNewest, &[])),
+            ["d", "a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_raw_html_stays_balanced() {
+        let content = "<details>\n<summary>More</summary>\n\n<b>bold *and* plain</b>\n\n</details>\n\nAn <i>unclosed tag\n\n</div></div>\n\n- <span>item\n";
+        let html = BlogPost::render_content(content, false);
+
+        // Elements may span markdown blocks, but never leak out of the container they
+        // were opened in, and stray closing tags are dropped
+        assert_eq!(
+            html,
+            "<details>\n<summary>More</summary>\n<p><b>bold <em>and</em> plain</b></p>\n</details>\n\
+             <p>An <i>unclosed tag</i></p>\n\n<ul>\n<li><span>item</span></li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn test_hostile_post_cannot_inject_markup() {
+        let source = r#"---
+title: "Using <T> in Rust</title><script>alert(1)</script>"
+excerpt: "\"><img src=x onerror=alert(1)>"
+author: "<b>Mallory</b>"
+tags: ["<i>tag</i>", "x\" onmouseover=\"alert(1)"]
+published_at: "2025-01-01 10:00:00"
+---
+
+Inline <script>alert(1)</script> and a block:
+
+<iframe src="https://evil.example"></iframe>
+
+[[image src='/a.png" onerror="alert(1)' alt="<script>" caption="<b>cap</b>"]]
+
+[x](javascript:alert(1)) <javascript:alert(2)> ![i](javascript:alert(3))
+"#;
+        let (meta, content) = BlogPost::parse_frontmatter(source).unwrap();
+        let post = BlogPost {
+            slug: "hostile".to_string(),
+            meta,
+            html: BlogPost::render_content(&content, false),
+            content,
+        };
+
+        // The body of an inline script is dropped with its tags
+        assert!(post.html.contains("Inline  and a block"), "{}", post.html);
+        assert!(!post.html.contains("=\"javascript:"), "{}", post.html);
+        assert!(
+            post.html.contains(r#"<a href="">javascript:alert(2)</a>"#),
+            "{}",
+            post.html
+        );
+        assert!(post.html.contains(r#"<a href="">x</a>"#), "{}", post.html);
+        assert!(
+            post.html.contains(r#"<img src="" alt="i" />"#),
+            "{}",
+            post.html
+        );
+        assert!(!post.html.contains("iframe"));
+        assert!(BlogPost::render_content(&post.content, true).contains("<iframe src="));
+        assert!(
+            post.html
+                .contains(r#"<img src="/a.png&quot; onerror=&quot;alert(1)" alt="&lt;script&gt;""#)
+        );
+        assert!(
+            post.html
+                .contains("<figcaption>&lt;b&gt;cap&lt;/b&gt;</figcaption>")
+        );
+
+        let engine = TemplateEngine::new(None).unwrap();
+        let mut context = Context::new();
+        context.insert("post", &post);
+        context.insert("content", Value::safe(post.html.as_str()));
+        context.insert("posts", vec![Value::from(&post)]);
+        context.insert("page", &PageMeta::for_post("https://example.com", &post));
+
+        for page in ["blog_post.html", "homepage.html", "404.html"] {
+            let html = engine.render(page, &context).unwrap();
+            for injected in [
+                "<script>",
+                "<img src=x",
+                "<b>Mallory",
+                "<i>tag",
+                "\"x\" onmouseover",
+                "<iframe",
+                "</title><script",
+            ] {
+                assert!(!html.contains(injected), "{page} contains {injected}");
+            }
+        }
     }
 }
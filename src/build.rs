@@ -0,0 +1,303 @@
+//! Static site export
+//!
+//! Renders every route with the same handlers and templates as the server and writes
+//! the result to an output directory that any static host can serve. Pretty URLs such
+//! as `/blog/<slug>` become `blog/<slug>/index.html`.
+
+use crate::app;
+use crate::config::{Config, Mode};
+use crate::handlers;
+use crate::routes;
+use log::{debug, info};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+type BuildResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Marker file identifying a directory as build output that is safe to replace
+const BUILD_MARKER: &str = ".nornity-build";
+
+/// Options for a static export
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    pub out_dir: PathBuf,
+    pub base_url: Option<String>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            out_dir: PathBuf::from("dist"),
+            base_url: None,
+        }
+    }
+}
+
+/// Summary of a finished export
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    pub pages: usize,
+    pub static_files: usize,
+}
+
+/// Render the whole site into `options.out_dir`
+///
+/// Fails if any post does not parse, or on the first page that does not render, leaving
+/// a partial output directory.
+pub async fn build_site(mut config: Config, options: &BuildOptions) -> BuildResult<BuildReport> {
+    if let Some(base_url) = &options.base_url {
+        config.base_url = base_url.trim_end_matches('/').to_string();
+    }
+    // Drafts and unminified stylesheets are for local previews and never go into an export
+    config.drafts = false;
+    config.mode = Mode::Production;
+    info!(
+        "Building static site into {:?} for {}",
+        options.out_dir, config.base_url
+    );
+    routes::set_config(config.clone());
+
+    let failed = app::init_blog_store(&config.content_dir)
+        .await
+        .map_err(|e| e.to_string())?;
+    // The server keeps running without a broken post, but an export must not lose one
+    if !failed.is_empty() {
+        let paths: Vec<_> = failed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        return Err(format!(
+            "Failed to load {} post(s): {}",
+            failed.len(),
+            paths.join(", ")
+        )
+        .into());
+    }
+    // Rebuilt rather than initialized, so they always follow the export's config
+    routes::reload_template_engine()?;
+    routes::reload_assets()?;
+
+    prepare_out_dir(&options.out_dir)?;
+    let out = options.out_dir.as_path();
+    let mut report = BuildReport::default();
+
+    write_page(out, "/", handlers::render_homepage(), &mut report)?;
+    write_page(out, "/blog", handlers::render_blog_list(""), &mut report)?;
+
+    let snapshot = routes::get_blog_store().snapshot();
+    for post in snapshot.posts_by_date() {
+        let route = format!("/blog/{}", post.slug);
+        write_page(out, &route, handlers::render_blog_post(post), &mut report)?;
+        let card = handlers::render_og_image(post)
+            .map_err(|e| format!("Failed to render share card for {route}: {e}"))?;
+        write_output(
+            out,
+            &Path::new(route.trim_matches('/')).join("og.png"),
+            card.png.as_slice(),
+        )?;
+    }
+
+    write_file(out, "404.html", handlers::render_not_found(), &mut report)?;
+    write_file(out, "sitemap.xml", handlers::render_sitemap(), &mut report)?;
+    write_file(out, "rss.xml", handlers::render_rss_feed(), &mut report)?;
+    write_file(
+        out,
+        "robots.txt",
+        Ok(handlers::render_robots_txt()),
+        &mut report,
+    )?;
+
+    let static_dir = Path::new(&config.static_dir);
+    if static_dir.is_dir() {
+        let static_out = out.join("static");
+        report.static_files = copy_dir(static_dir, &static_out)?;
+        // Pages link fingerprinted URLs, which a static host serves as plain files
+        let assets = routes::get_assets();
+        for (logical, fingerprinted) in assets.fingerprinted_paths() {
+            if let Some(css) = assets.stylesheet(logical) {
+                // Stylesheets are exported as bundles under both names
+                write_output(&static_out, Path::new(logical), css.as_bytes())?;
+                write_output(&static_out, Path::new(&fingerprinted), css.as_bytes())?;
+                continue;
+            }
+            fs::copy(static_dir.join(logical), static_out.join(&fingerprinted))
+                .map_err(|e| format!("Failed to copy {logical} to {fingerprinted}: {e}"))?;
+        }
+    } else {
+        log::warn!("Static directory {static_dir:?} does not exist, skipping");
+    }
+
+    fs::write(out.join(BUILD_MARKER), "")?;
+    info!(
+        "Built {} pages and copied {} static files",
+        report.pages, report.static_files
+    );
+    Ok(report)
+}
+
+/// Create an empty output directory, replacing a previous build but nothing else
+fn prepare_out_dir(out_dir: &Path) -> BuildResult<()> {
+    if out_dir.exists() {
+        let is_empty = fs::read_dir(out_dir)?.next().is_none();
+        if !is_empty && !out_dir.join(BUILD_MARKER).is_file() {
+            return Err(format!(
+                "Refusing to overwrite {out_dir:?}: it is not empty and was not created by a build"
+            )
+            .into());
+        }
+        debug!("Removing previous build in {out_dir:?}");
+        fs::remove_dir_all(out_dir)?;
+    }
+    fs::create_dir_all(out_dir)?;
+    Ok(())
+}
+
+/// Write a page for a pretty URL route as `<route>/index.html`
+fn write_page(
+    out_dir: &Path,
+    route: &str,
+    rendered: handlers::RenderResult,
+    report: &mut BuildReport,
+) -> BuildResult<()> {
+    let relative = Path::new(route.trim_matches('/')).join("index.html");
+    let html = rendered.map_err(|e| format!("Failed to render {route}: {e}"))?;
+    write_output(out_dir, &relative, &html)?;
+    report.pages += 1;
+    Ok(())
+}
+
+/// Write a route that maps to a single file, such as `sitemap.xml`
+fn write_file(
+    out_dir: &Path,
+    name: &str,
+    rendered: handlers::RenderResult,
+    report: &mut BuildReport,
+) -> BuildResult<()> {
+    let body = rendered.map_err(|e| format!("Failed to render /{name}: {e}"))?;
+    write_output(out_dir, Path::new(name), &body)?;
+    report.pages += 1;
+    Ok(())
+}
+
+fn write_output(out_dir: &Path, relative: &Path, body: impl AsRef<[u8]>) -> BuildResult<()> {
+    let path = out_dir.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, body).map_err(|e| format!("Failed to write {path:?}: {e}"))?;
+    debug!("Wrote {path:?}");
+    Ok(())
+}
+
+/// Recursively copy a directory, returning the number of files copied
+fn copy_dir(from: &Path, to: &Path) -> BuildResult<usize> {
+    fs::create_dir_all(to)?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copied += copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("Failed to copy {:?}: {e}", entry.path()))?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, body: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, body).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_build_site() {
+        let dir = std::env::temp_dir().join(format!("nornity-build-{}", std::process::id()));
+        write(
+            &dir.join("content/hello.md"),
+            "---\ntitle: \"Hello\"\nexcerpt: \"Excerpt\"\nauthor: \"Ada\"\ntags: [\"rust\"]\npublished_at: \"2025-01-01 10:00:00\"\n---\n\nBody.\n",
+        );
+        write(
+            &dir.join("static/css/style.css"),
+            "body {\n  color: red;\n}\n",
+        );
+        // Inlined by the default post template
+        write(
+            &dir.join("static/css/blog-post.css"),
+            "main { margin: 0; }\n",
+        );
+        write(&dir.join("static/notes.txt"), "notes");
+        let config = Config {
+            content_dir: dir.join("content"),
+            static_dir: dir.join("static").to_string_lossy().into_owned(),
+            ..Config::default()
+        };
+        let options = BuildOptions {
+            out_dir: dir.join("dist"),
+            base_url: Some("https://example.com/".to_string()),
+        };
+
+        let report = build_site(config.clone(), &options).await.unwrap();
+        let out = options.out_dir.as_path();
+        for file in [
+            "index.html",
+            "blog/index.html",
+            "blog/hello/index.html",
+            "blog/hello/og.png",
+            "404.html",
+            "sitemap.xml",
+            "rss.xml",
+            "robots.txt",
+            "static/notes.txt",
+            BUILD_MARKER,
+        ] {
+            assert!(out.join(file).is_file(), "missing {file}");
+        }
+        assert_eq!(report.pages, 7);
+        let sitemap = fs::read_to_string(out.join("sitemap.xml")).unwrap();
+        assert!(
+            sitemap.contains("https://example.com/blog/hello"),
+            "{sitemap}"
+        );
+
+        // Fingerprinted copies sit next to the originals, with stylesheets minified
+        let assets = routes::get_assets();
+        let fingerprinted: Vec<_> = assets.fingerprinted_paths().collect();
+        assert_eq!(fingerprinted.len(), 3);
+        for (logical, fingerprinted) in &fingerprinted {
+            let copy = fs::read(out.join("static").join(fingerprinted)).unwrap();
+            assert_eq!(copy, fs::read(out.join("static").join(logical)).unwrap());
+        }
+        assert_eq!(
+            fs::read_to_string(out.join("static/css/style.css")).unwrap(),
+            "body{color:red}"
+        );
+
+        // A previous build is replaced, but nothing else is
+        build_site(config.clone(), &options).await.unwrap();
+        let other = BuildOptions {
+            out_dir: dir.join("other"),
+            base_url: None,
+        };
+        write(&other.out_dir.join("keep.txt"), "");
+        let err = build_site(config.clone(), &other).await.unwrap_err();
+        assert!(err.to_string().contains("Refusing to overwrite"), "{err}");
+        assert!(other.out_dir.join("keep.txt").is_file());
+
+        // A post that fails to parse fails the build instead of going missing
+        write(
+            &dir.join("content/broken.md"),
+            "---\ntitle: \"Broken\"\n---\n",
+        );
+        let err = build_site(config, &options).await.unwrap_err();
+        assert!(err.to_string().contains("broken.md"), "{err}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
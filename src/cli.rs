@@ -0,0 +1,440 @@
+//! Command-line interface
+//!
+//! Hand-rolled argument parsing for the `nornity` binary. Running it without a
+//! subcommand starts the server, as it always has.
+
+use crate::blog::scan_posts;
+use crate::build::BuildOptions;
+use crate::components::codeblock::{LexerFactory, fence_languages};
+use crate::config::{Config, Overrides, parse_host};
+use crate::templates::TemplateEngine;
+use chrono::Utc;
+use std::io::Write;
+use std::path::PathBuf;
+
+type CliResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Usage text printed by `nornity help`
+pub const USAGE: &str = "\
+Usage: nornity [COMMAND] [OPTIONS]
+
+Commands:
+  serve               Run the web server (default)
+  new <slug>          Create a new post in the content directory
+  list                List all posts with their status and date
+  build               Render the site into a directory for static hosting
+  check               Validate templates and every post without serving
+  help                Show this message
+
+Options for every command:
+  --config <file>     Read settings from this file instead of config.toml/config.conf
+
+serve:
+  --host <ip>         Address to listen on, e.g. 127.0.0.1
+  --port <port>       Port to listen on
+  --drafts            Serve draft posts too
+
+new:
+  --title <title>     Post title (defaults to the slug in title case)
+  --tags <a,b,c>      Comma-separated tags
+  --excerpt <text>    Short description shown in listings and feeds
+  --author <name>     Author (defaults to `author` from the config)
+  --draft             Mark the post as a draft
+
+build:
+  --out <dir>         Output directory (default: dist)
+  --base-url <url>    Override the configured base URL
+";
+
+/// A parsed command line
+#[derive(Debug, PartialEq)]
+pub struct Cli {
+    pub command: Command,
+    pub overrides: Overrides,
+}
+
+/// Subcommands of the `nornity` binary
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Serve,
+    New(NewPost),
+    List,
+    Build(BuildOptions),
+    Check,
+    Help,
+    Version,
+}
+
+/// Options for `nornity new`
+#[derive(Debug, Default, PartialEq)]
+pub struct NewPost {
+    pub slug: String,
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub excerpt: Option<String>,
+    pub author: Option<String>,
+    pub draft: bool,
+}
+
+/// Parse the arguments after the program name
+pub fn parse(args: &[String]) -> Result<Cli, String> {
+    // `--config` may also come before the subcommand
+    let mut leading = Vec::new();
+    let mut args = args;
+    while let Some(first) = args.first()
+        && (first == "--config" || first.starts_with("--config="))
+    {
+        let take = if first == "--config" { 2 } else { 1 };
+        let take = take.min(args.len());
+        leading.extend_from_slice(&args[..take]);
+        args = &args[take..];
+    }
+
+    // Flags without a subcommand belong to `serve`, which is the default
+    let (name, rest) = match args.first() {
+        Some(first) if !first.starts_with('-') => (first.as_str(), &args[1..]),
+        _ => ("serve", args),
+    };
+    leading.extend_from_slice(rest);
+    let rest = leading;
+
+    let mut command = match name {
+        "serve" => Command::Serve,
+        "new" => Command::New(NewPost::default()),
+        "list" => Command::List,
+        "build" => Command::Build(BuildOptions::default()),
+        "check" => Command::Check,
+        "help" => Command::Help,
+        _ => return Err(format!("Unknown command: {name}")),
+    };
+    let mut overrides = Overrides::default();
+    let mut positional = Vec::new();
+
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with('-') {
+            positional.push(arg.clone());
+            continue;
+        }
+
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag, Some(value.to_string())),
+            None => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| rest.next().cloned())
+                .ok_or_else(|| format!("Missing value for {flag}"))
+        };
+
+        match (&mut command, flag) {
+            (_, "-h" | "--help") => command = Command::Help,
+            (_, "-V" | "--version") => command = Command::Version,
+            (Command::Help | Command::Version, _) => {}
+            (_, "--config") => overrides.config_file = Some(PathBuf::from(value()?)),
+            (Command::Serve, "--host") => {
+                let host = value()?;
+                overrides.host =
+                    Some(parse_host(&host).ok_or_else(|| format!("Invalid host: {host}"))?);
+            }
+            (Command::Serve, "--port") => {
+                let port = value()?;
+                overrides.port = Some(port.parse().map_err(|_| format!("Invalid port: {port}"))?);
+            }
+            (Command::Serve, "--drafts") => overrides.drafts = true,
+            (Command::New(post), "--title") => post.title = Some(value()?),
+            (Command::New(post), "--tags") => {
+                post.tags = value()?
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            (Command::New(post), "--excerpt") => post.excerpt = Some(value()?),
+            (Command::New(post), "--author") => post.author = Some(value()?),
+            (Command::New(post), "--draft") => post.draft = true,
+            (Command::Build(options), "--out" | "-o") => options.out_dir = PathBuf::from(value()?),
+            (Command::Build(options), "--base-url") => options.base_url = Some(value()?),
+            _ => return Err(format!("Unknown option for {name}: {arg}")),
+        }
+    }
+
+    match &mut command {
+        Command::New(post) => match positional.as_slice() {
+            [slug] => post.slug = slug.clone(),
+            [] => return Err("Missing slug: nornity new <slug>".to_string()),
+            _ => return Err(format!("Expected one slug, got: {}", positional.join(" "))),
+        },
+        Command::Help | Command::Version => {}
+        _ if !positional.is_empty() => {
+            return Err(format!("Unexpected argument: {}", positional[0]));
+        }
+        _ => {}
+    }
+
+    Ok(Cli { command, overrides })
+}
+
+/// Create a new post file, refusing to reuse an existing slug
+pub async fn new_post(config: &Config, options: &NewPost) -> CliResult<PathBuf> {
+    let slug = options.slug.as_str();
+    validate_slug(slug)?;
+
+    // Slugs come from file names, drafts and unparseable posts included, so the
+    // target file is the only thing a new post can collide with
+    let path = config.content_dir.join(format!("{slug}.md"));
+    if path.exists() {
+        return Err(format!("A post with slug '{slug}' already exists").into());
+    }
+
+    let title = options
+        .title
+        .clone()
+        .unwrap_or_else(|| title_from_slug(slug));
+    let excerpt = options.excerpt.clone().unwrap_or_default();
+    let author = options.author.as_deref().unwrap_or(&config.author);
+    for (field, value) in [("title", &title), ("excerpt", &excerpt)] {
+        validate_frontmatter_value(field, value)?;
+    }
+    validate_frontmatter_value("author", author)?;
+    for tag in &options.tags {
+        if tag.contains(['"', '[', ']']) {
+            return Err(format!("Invalid tag: {tag}").into());
+        }
+    }
+
+    let tags = options
+        .tags
+        .iter()
+        .map(|tag| format!("\"{tag}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let published_at = Utc::now().format("%Y-%m-%d %H:%M:%S");
+    let post = format!(
+        "---\n\
+         title: \"{title}\"\n\
+         excerpt: \"{excerpt}\"\n\
+         author: \"{author}\"\n\
+         tags: [{tags}]\n\
+         published_at: \"{published_at}\"\n\
+         draft: {draft}\n\
+         ---\n\
+         \n\
+         # {title}\n\
+         \n\
+         Your content goes here...\n",
+        draft = options.draft,
+    );
+
+    std::fs::create_dir_all(&config.content_dir)?;
+    // create_new guards against a file appearing between the check and the write
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    file.write_all(post.as_bytes())?;
+    Ok(path)
+}
+
+/// Print every post, drafts and unparseable files included
+pub async fn list_posts(config: &Config) -> CliResult<()> {
+    if !config.content_dir.is_dir() {
+        return Err(format!(
+            "Content directory does not exist: {}",
+            config.content_dir.display()
+        )
+        .into());
+    }
+
+    let mut posts = Vec::new();
+    let mut broken = Vec::new();
+    for scanned in scan_posts(&config.content_dir).await? {
+        match scanned.post {
+            Ok(post) => posts.push(post),
+            Err(e) => broken.push((scanned.path, e)),
+        }
+    }
+    posts.sort_by_key(|post| std::cmp::Reverse(post.meta.published_at));
+
+    if posts.is_empty() && broken.is_empty() {
+        println!("No posts in {}", config.content_dir.display());
+        return Ok(());
+    }
+
+    let slug_width = posts.iter().map(|p| p.slug.len()).max().unwrap_or(0);
+    for post in &posts {
+        let status = if post.is_published() {
+            "published"
+        } else {
+            "draft"
+        };
+        println!(
+            "{status:<9}  {}  {:<slug_width$}  {}",
+            post.meta.published_at.format("%Y-%m-%d %H:%M"),
+            post.slug,
+            post.title()
+        );
+    }
+    for (path, error) in &broken {
+        println!("{:<9}  {}: {error}", "error", path.display());
+    }
+    Ok(())
+}
+
+/// Validate templates and every post, returning the number of problems found
+pub async fn check(config: &Config) -> CliResult<usize> {
+    let mut problems = 0;
+
+    match TemplateEngine::new(config.theme.as_deref()) {
+        Ok(_) => println!("ok       templates"),
+        Err(e) => {
+            problems += 1;
+            println!("error    templates: {e}");
+        }
+    }
+
+    if !config.content_dir.is_dir() {
+        println!(
+            "error    content directory does not exist: {}",
+            config.content_dir.display()
+        );
+        return Ok(problems + 1);
+    }
+
+    let scanned = scan_posts(&config.content_dir).await?;
+    let mut unknown_languages = false;
+    for entry in &scanned {
+        match &entry.post {
+            Ok(post) => {
+                // Unknown languages still render, just without highlighting
+                for language in fence_languages(&post.content) {
+                    if LexerFactory::find(&language).is_none() {
+                        unknown_languages = true;
+                        println!(
+                            "warning  {}: no highlighting for code block language '{language}'",
+                            entry.path.display()
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                problems += 1;
+                println!("error    {}: {e}", entry.path.display());
+            }
+        }
+    }
+    println!(
+        "checked {} post(s): {} ok",
+        scanned.len(),
+        scanned.iter().filter(|entry| entry.post.is_ok()).count()
+    );
+    if unknown_languages {
+        let names: Vec<&str> = LexerFactory::languages()
+            .iter()
+            .map(|language| language.name)
+            .collect();
+        println!("highlighted languages: {}", names.join(", "));
+    }
+
+    Ok(problems)
+}
+
+/// Slugs become file names and URL segments, so keep them to `a-z`, `0-9` and `-`
+fn validate_slug(slug: &str) -> CliResult<()> {
+    let valid_chars = slug
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if slug.is_empty() || !valid_chars || slug.starts_with('-') || slug.ends_with('-') {
+        return Err(format!(
+            "Invalid slug '{slug}': use lowercase letters, digits and inner hyphens"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The frontmatter parser reads one line per field and strips surrounding quotes
+fn validate_frontmatter_value(field: &str, value: &str) -> CliResult<()> {
+    if value.contains('\n') || value.starts_with('"') || value.ends_with('"') {
+        return Err(format!(
+            "Invalid {field}: must be a single line and not start or end with a quote"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Turn `my-first-post` into `My First Post`
+fn title_from_slug(slug: &str) -> String {
+    slug.split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let cli = parse(&[]).unwrap();
+        assert_eq!(cli.command, Command::Serve);
+
+        let cli = parse(&args(&["--port", "8080", "--drafts"])).unwrap();
+        assert_eq!(cli.command, Command::Serve);
+        assert_eq!(cli.overrides.port, Some(8080));
+        assert!(cli.overrides.drafts);
+
+        let cli = parse(&args(&["new", "my-post", "--tags=rust, os", "--draft"])).unwrap();
+        let Command::New(post) = cli.command else {
+            panic!("expected new");
+        };
+        assert_eq!(post.slug, "my-post");
+        assert_eq!(post.tags, vec!["rust", "os"]);
+        assert!(post.draft);
+
+        let cli = parse(&args(&[
+            "build",
+            "--out",
+            "public",
+            "--base-url=https://a.b",
+        ]))
+        .unwrap();
+        let Command::Build(options) = cli.command else {
+            panic!("expected build");
+        };
+        assert_eq!(options.out_dir, PathBuf::from("public"));
+        assert_eq!(options.base_url.as_deref(), Some("https://a.b"));
+
+        let cli = parse(&args(&["--config", "site.toml", "list"])).unwrap();
+        assert_eq!(cli.command, Command::List);
+        assert_eq!(cli.overrides.config_file, Some(PathBuf::from("site.toml")));
+
+        assert!(parse(&args(&["new"])).is_err());
+        assert!(parse(&args(&["list", "--port", "1"])).is_err());
+        assert!(parse(&args(&["build", "--out"])).is_err());
+        assert!(parse(&args(&["deploy"])).is_err());
+    }
+
+    #[test]
+    fn test_slug_and_title() {
+        assert!(validate_slug("my-post-2").is_ok());
+        assert!(validate_slug("My Post").is_err());
+        assert!(validate_slug("-post").is_err());
+        assert!(validate_slug("../post").is_err());
+        assert_eq!(title_from_slug("my-first-post"), "My First Post");
+    }
+}
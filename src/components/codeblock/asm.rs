@@ -0,0 +1,360 @@
+use super::LanguageLexer;
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+
+/// x86-64 in GAS/AT&T or Intel/NASM syntax
+pub struct X86AsmLexer;
+
+/// AArch64 in GAS or Apple syntax
+pub struct Arm64AsmLexer;
+
+/// RISC-V in GAS syntax
+pub struct RiscVAsmLexer;
+
+/// What differs between the assembly dialects
+struct Syntax {
+    line_comments: &'static [&'static str],
+    /// Prefix of immediates, e.g. `$42` or `#42`
+    immediate: Option<char>,
+    /// Whether `%name` is a register (AT&T) rather than a relocation like `%hi(sym)`
+    percent_registers: bool,
+    /// Instruction prefixes that are followed by the actual mnemonic
+    prefixes: &'static [&'static str],
+    /// Operand size and distance specifiers
+    specifiers: &'static [&'static str],
+    is_register: fn(&str) -> bool,
+}
+
+impl LanguageLexer for X86AsmLexer {
+    fn get_keywords(&self) -> &[&str] {
+        // NASM directives, which are written without a leading dot
+        &[
+            "section", "segment", "global", "extern", "bits", "default", "align", "db", "dw", "dd",
+            "dq", "dt", "resb", "resw", "resd", "resq", "equ", "times", "incbin",
+        ]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        lex_asm(
+            input,
+            self.get_keywords(),
+            &Syntax {
+                line_comments: &["#", ";", "//"],
+                immediate: Some('$'),
+                percent_registers: true,
+                prefixes: &[
+                    "lock", "rep", "repe", "repz", "repne", "repnz", "data16", "rex64",
+                ],
+                specifiers: &[
+                    "byte", "word", "dword", "qword", "tword", "oword", "xmmword", "ymmword",
+                    "zmmword", "ptr", "offset", "rel", "short", "near", "far",
+                ],
+                is_register: is_x86_register,
+            },
+        )
+    }
+}
+
+impl LanguageLexer for Arm64AsmLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        lex_asm(
+            input,
+            self.get_keywords(),
+            &Syntax {
+                line_comments: &["//", ";"],
+                immediate: Some('#'),
+                percent_registers: false,
+                prefixes: &[],
+                specifiers: &["lsl", "lsr", "asr", "ror", "uxtw", "sxtw", "uxtx", "sxtx"],
+                is_register: is_arm64_register,
+            },
+        )
+    }
+}
+
+impl LanguageLexer for RiscVAsmLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        lex_asm(
+            input,
+            self.get_keywords(),
+            &Syntax {
+                line_comments: &["#", "//"],
+                immediate: None,
+                percent_registers: false,
+                prefixes: &[],
+                specifiers: &[],
+                is_register: is_riscv_register,
+            },
+        )
+    }
+}
+
+/// Lex assembly a statement at a time
+///
+/// The first word of a statement is a label if followed by `:`, a directive if it starts
+/// with `.` (or is a keyword), and otherwise the mnemonic; the rest are operands.
+fn lex_asm(input: &str, keywords: &[&str], syntax: &Syntax) -> Vec<Token> {
+    let mut lexer = BaseLexer::new(input);
+    // Whether the next word starts a statement
+    let mut statement_start = true;
+
+    while lexer.position < lexer.input.len() {
+        let ch = lexer.current_char().unwrap_or('\0');
+        if let Some(prefix) = syntax.line_comments.iter().find(|prefix| {
+            prefix
+                .chars()
+                .enumerate()
+                .all(|(i, c)| lexer.peek_char(i) == Some(c))
+        }) {
+            lexer.consume_single_line_comment(prefix);
+            continue;
+        }
+
+        match ch {
+            ' ' | '\t' => {
+                lexer.consume_whitespace();
+            }
+            '\n' | '\r' => {
+                lexer.consume_newlines();
+                statement_start = true;
+            }
+            '/' if lexer.peek_char(1) == Some('*') => {
+                lexer.consume_multi_line_comment("/*", "*/");
+            }
+            '"' | '\'' => {
+                lexer.consume_string(ch);
+            }
+            c if Some(c) == syntax.immediate => {
+                let start = lexer.position;
+                lexer.advance();
+                let mut value = c.to_string();
+                if lexer.current_char() == Some('-') {
+                    value.push('-');
+                    lexer.advance();
+                }
+                value.push_str(&lexer.take_while(is_word_char));
+                lexer.add_token(TokenType::Number, value, start, lexer.position);
+            }
+            '%' if lexer.peek_char(1).is_some_and(|c| c.is_ascii_alphabetic()) => {
+                let start = lexer.position;
+                lexer.advance();
+                let value = format!("%{}", lexer.take_while(is_word_char));
+                let token_type = if syntax.percent_registers {
+                    TokenType::Variable
+                } else {
+                    // RISC-V relocation such as %hi(sym) or %pcrel_lo(1b)
+                    TokenType::Macro
+                };
+                lexer.add_token(token_type, value, start, lexer.position);
+            }
+            '0'..='9' => {
+                // Numbers, NASM suffixes like 10h, and local label references like 1f
+                let start = lexer.position;
+                let value = lexer.take_while(is_word_char);
+                let token_type = if statement_start && lexer.current_char() == Some(':') {
+                    TokenType::Function
+                } else {
+                    TokenType::Number
+                };
+                lexer.add_token(token_type, value, start, lexer.position);
+            }
+            c if c.is_alphabetic() || matches!(c, '_' | '.' | '$') => {
+                let start = lexer.position;
+                let value = lexer.take_while(is_word_char);
+                let lower = value.to_ascii_lowercase();
+                let token_type = if statement_start && lexer.current_char() == Some(':') {
+                    TokenType::Function
+                } else if statement_start
+                    && (value.starts_with('.') || keywords.contains(&lower.as_str()))
+                {
+                    statement_start = false;
+                    TokenType::Macro
+                } else if statement_start {
+                    statement_start = syntax.prefixes.contains(&lower.as_str());
+                    TokenType::Keyword
+                } else if keywords.contains(&lower.as_str()) {
+                    TokenType::Macro
+                } else if (syntax.is_register)(lower.split('.').next().unwrap_or_default()) {
+                    TokenType::Variable
+                } else if syntax.specifiers.contains(&lower.as_str()) {
+                    TokenType::Type
+                } else {
+                    TokenType::Identifier
+                };
+                lexer.add_token(token_type, value, start, lexer.position);
+            }
+            ';' => {
+                // Statement separator where `;` does not start a comment
+                lexer.add_operator(ch.to_string());
+                statement_start = true;
+            }
+            ',' | ':' | '[' | ']' | '(' | ')' | '{' | '}' | '+' | '-' | '*' | '/' | '!' | '='
+            | '<' | '>' | '&' | '|' | '^' | '~' => {
+                lexer.add_operator(ch.to_string());
+            }
+            _ => {
+                lexer.add_other(ch.to_string());
+            }
+        }
+    }
+    lexer.get_tokens()
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$' | '@')
+}
+
+/// `name` is `prefix` followed by a number up to `max`
+fn numbered(name: &str, prefix: &str, max: u32) -> bool {
+    name.strip_prefix(prefix)
+        .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .and_then(|n| n.parse::<u32>().ok())
+        .is_some_and(|n| n <= max)
+}
+
+fn is_x86_register(name: &str) -> bool {
+    const NAMED: &[&str] = &[
+        "rax", "rbx", "rcx", "rdx", "rsi", "rdi", "rbp", "rsp", "eax", "ebx", "ecx", "edx", "esi",
+        "edi", "ebp", "esp", "ax", "bx", "cx", "dx", "si", "di", "bp", "sp", "al", "ah", "bl",
+        "bh", "cl", "ch", "dl", "dh", "sil", "dil", "bpl", "spl", "rip", "eip", "ip", "cs", "ds",
+        "es", "fs", "gs", "ss", "st", "rflags", "eflags",
+    ];
+    if NAMED.contains(&name) {
+        return true;
+    }
+    // r8 to r15, with a d, w, b or l suffix for the narrower views
+    let wide = name.strip_suffix(['d', 'w', 'b', 'l']).unwrap_or(name);
+    if numbered(wide, "r", 15) && !numbered(wide, "r", 7) {
+        return true;
+    }
+    [
+        ("xmm", 31),
+        ("ymm", 31),
+        ("zmm", 31),
+        ("mm", 7),
+        ("st", 7),
+        ("k", 7),
+        ("cr", 15),
+        ("dr", 15),
+    ]
+    .iter()
+    .any(|&(prefix, max)| numbered(name, prefix, max))
+}
+
+fn is_arm64_register(name: &str) -> bool {
+    const NAMED: &[&str] = &[
+        "sp", "wsp", "xzr", "wzr", "lr", "fp", "pc", "nzcv", "fpcr", "fpsr", "ffr",
+    ];
+    NAMED.contains(&name)
+        || numbered(name, "x", 30)
+        || numbered(name, "w", 30)
+        || ["v", "q", "d", "s", "h", "b", "z"]
+            .iter()
+            .any(|prefix| numbered(name, prefix, 31))
+        || numbered(name, "p", 15)
+}
+
+fn is_riscv_register(name: &str) -> bool {
+    const NAMED: &[&str] = &["zero", "ra", "sp", "gp", "tp", "fp", "pc"];
+    NAMED.contains(&name)
+        || [
+            ("x", 31),
+            ("f", 31),
+            ("v", 31),
+            ("t", 6),
+            ("s", 11),
+            ("a", 7),
+            ("ft", 11),
+            ("fs", 11),
+            ("fa", 7),
+        ]
+        .iter()
+        .any(|&(prefix, max)| numbered(name, prefix, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_assembly_lexers() {
+        let gas = significant_tokens("gas", ".globl main\nmain:\n\tmovq $42, %rax # answer\n");
+        let gas: Vec<_> = gas.iter().map(|(t, v)| (t.clone(), v.as_str())).collect();
+        assert_eq!(
+            gas,
+            vec![
+                (TokenType::Macro, ".globl"),
+                (TokenType::Identifier, "main"),
+                (TokenType::Function, "main"),
+                (TokenType::Operator, ":"),
+                (TokenType::Keyword, "movq"),
+                (TokenType::Number, "$42"),
+                (TokenType::Operator, ","),
+                (TokenType::Variable, "%rax"),
+                (TokenType::Comment, "# answer"),
+            ]
+        );
+
+        let nasm = significant_tokens(
+            "nasm",
+            "section .text\nrep movsb\nmov rax, qword [r8d + 10h] ; c\n",
+        );
+        assert_eq!(nasm[0], (TokenType::Macro, "section".to_string()));
+        assert_eq!(nasm[2], (TokenType::Keyword, "rep".to_string()));
+        assert_eq!(nasm[3], (TokenType::Keyword, "movsb".to_string()));
+        assert!(
+            nasm.contains(&(TokenType::Variable, "r8d".to_string())),
+            "{nasm:?}"
+        );
+        assert!(
+            nasm.contains(&(TokenType::Type, "qword".to_string())),
+            "{nasm:?}"
+        );
+        assert!(
+            nasm.contains(&(TokenType::Number, "10h".to_string())),
+            "{nasm:?}"
+        );
+
+        let arm = significant_tokens(
+            "aarch64",
+            "1:\tb.ne 1b // loop\n\tadd v0.4s, v1.4s, v2.4s\n\tldr x0, [sp, #-16]!\n",
+        );
+        assert_eq!(arm[0], (TokenType::Function, "1".to_string()));
+        assert_eq!(arm[2], (TokenType::Keyword, "b.ne".to_string()));
+        assert!(
+            arm.contains(&(TokenType::Variable, "v0.4s".to_string())),
+            "{arm:?}"
+        );
+        assert!(
+            arm.contains(&(TokenType::Number, "#-16".to_string())),
+            "{arm:?}"
+        );
+
+        let riscv = significant_tokens("riscv", "lui a0, %hi(msg) # c\naddi sp, sp, -16; ret\n");
+        assert!(
+            riscv.contains(&(TokenType::Macro, "%hi".to_string())),
+            "{riscv:?}"
+        );
+        assert!(
+            riscv.contains(&(TokenType::Identifier, "msg".to_string())),
+            "{riscv:?}"
+        );
+        assert!(
+            riscv.contains(&(TokenType::Variable, "sp".to_string())),
+            "{riscv:?}"
+        );
+        assert!(
+            riscv.contains(&(TokenType::Keyword, "ret".to_string())),
+            "{riscv:?}"
+        );
+    }
+}
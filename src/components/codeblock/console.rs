@@ -0,0 +1,120 @@
+use super::LanguageLexer;
+use super::{TokenType, Token};
+use super::BashLexer;
+
+/// Terminal sessions: prompts, the commands typed after them, and their output
+///
+/// Commands are highlighted with [`BashLexer`]; everything else is output.
+pub struct ConsoleLexer;
+
+impl LanguageLexer for ConsoleLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        // Start of the current line
+        let mut offset = 0;
+        // Whether the previous command ended in `\` and goes on without a prompt
+        let mut continued = false;
+
+        for line in input.split_inclusive('\n') {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let newline = &line[text.len()..];
+            let prompt = if continued {
+                Some(0)
+            } else {
+                prompt_length(text)
+            };
+
+            match prompt {
+                Some(length) => {
+                    let (prompt, command) = text.split_at(length);
+                    let mut position = offset;
+                    if !prompt.is_empty() {
+                        let end = position + prompt.chars().count();
+                        tokens.push(Token::new(TokenType::Prompt, prompt, position, end));
+                        position = end;
+                    }
+                    tokens.extend(BashLexer.lex(command).into_iter().map(|mut token| {
+                        token.start += position;
+                        token.end += position;
+                        token
+                    }));
+                    if !newline.is_empty() {
+                        let start = position + command.chars().count();
+                        let end = start + newline.chars().count();
+                        tokens.push(Token::new(TokenType::Newline, newline, start, end));
+                    }
+                    continued = command.trim_end().ends_with('\\');
+                }
+                None => {
+                    // The newline belongs to the output, so copying leaves no blank line
+                    let end = offset + line.chars().count();
+                    tokens.push(Token::new(TokenType::Output, line, offset, end));
+                }
+            }
+            offset += line.chars().count();
+        }
+        tokens
+    }
+}
+
+/// Byte length of the prompt at the start of `line`, including one space after it
+///
+/// Prompts are a bare `$`, `#` or `>`, or one after a `user@host:~/dir` or
+/// `[user@host dir]` prefix.
+fn prompt_length(line: &str) -> Option<usize> {
+    const PROMPTS: [char; 3] = ['$', '#', '>'];
+    let end = line.find(PROMPTS)?;
+    let prefix = &line[..end];
+    let bracketed = prefix.starts_with('[') && prefix.ends_with(']');
+    let user_host = !prefix.contains(char::is_whitespace) && prefix.contains(['@', ':', '~']);
+    if !prefix.is_empty() && !bracketed && !user_host {
+        return None;
+    }
+
+    let rest = &line[end + 1..];
+    if rest.is_empty() {
+        Some(end + 1)
+    } else if rest.starts_with(' ') {
+        Some(end + 2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::process_markdown_content;
+
+    #[test]
+    fn test_console_lexer() {
+        let html = process_markdown_content(
+            "```console\n$ sudo apt update\nHit:1 http://deb.debian.org stable InRelease\n\
+             user@host:~/src$ make \\\n  -j4\n[root@host /]# exit\n```\n",
+            None,
+        );
+        assert!(
+            html.contains("<span class=\"prompt\">$ </span><span class=\"command\">sudo</span>"),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<span class=\"output\">Hit:1 http://deb.debian.org stable InRelease\n</span>"
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains("<span class=\"prompt\">user@host:~/src$ </span>"),
+            "{html}"
+        );
+        assert!(
+            html.contains("<span class=\"prompt\">[root@host /]# </span>"),
+            "{html}"
+        );
+        // A continued command has no prompt but is still a command
+        assert!(!html.contains("<span class=\"output\">  -j4"), "{html}");
+    }
+}
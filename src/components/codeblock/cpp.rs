@@ -151,14 +151,11 @@ impl LanguageLexer for CppLexer {
                     lexer.advance();
                     
                     // Check for stream operators and compound operators
-                    if let Some(next_ch) = lexer.current_char() {
-                        if next_ch == '<' {
-                            value.push(next_ch);
-                            lexer.advance();
-                        } else if next_ch == '=' {
-                            value.push(next_ch);
-                            lexer.advance();
-                        }
+                    if let Some(next_ch) = lexer.current_char()
+                        && (next_ch == '<' || next_ch == '=')
+                    {
+                        value.push(next_ch);
+                        lexer.advance();
                     }
                     
                     lexer.add_token(TokenType::Operator, value, start, lexer.position);
@@ -170,14 +167,11 @@ impl LanguageLexer for CppLexer {
                     lexer.advance();
                     
                     // Check for stream operators and compound operators
-                    if let Some(next_ch) = lexer.current_char() {
-                        if next_ch == '>' {
-                            value.push(next_ch);
-                            lexer.advance();
-                        } else if next_ch == '=' {
-                            value.push(next_ch);
-                            lexer.advance();
-                        }
+                    if let Some(next_ch) = lexer.current_char()
+                        && (next_ch == '>' || next_ch == '=')
+                    {
+                        value.push(next_ch);
+                        lexer.advance();
                     }
                     
                     lexer.add_token(TokenType::Operator, value, start, lexer.position);
@@ -188,11 +182,11 @@ impl LanguageLexer for CppLexer {
                     let mut value = String::from(ch);
                     lexer.advance();
                     
-                    if let Some(next_ch) = lexer.current_char() {
-                        if next_ch == ':' {
-                            value.push(next_ch);
-                            lexer.advance();
-                        }
+                    if let Some(next_ch) = lexer.current_char()
+                        && next_ch == ':'
+                    {
+                        value.push(next_ch);
+                        lexer.advance();
                     }
                     
                     lexer.add_token(TokenType::Operator, value, start, lexer.position);
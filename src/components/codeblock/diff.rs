@@ -0,0 +1,241 @@
+use super::LanguageLexer;
+use super::{TokenType, Token};
+
+/// Unified diffs and patches, optionally highlighting the code in each line with another
+/// lexer, as in `diff-rust`
+pub struct DiffLexer {
+    inner: Option<Box<dyn LanguageLexer>>,
+}
+
+impl DiffLexer {
+    pub fn new(inner: Option<Box<dyn LanguageLexer>>) -> Self {
+        Self { inner }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LineKind {
+    /// `diff --git`, `index`, `---`/`+++` and the like
+    FileHeader,
+    /// `@@ -1,4 +1,5 @@`
+    Hunk,
+    Inserted,
+    Deleted,
+    /// Unchanged line inside a hunk
+    Context,
+    /// `\ No newline at end of file`
+    Note,
+    /// Anything else, such as the message of a mailed patch
+    Other,
+}
+
+impl LanguageLexer for DiffLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut offset = 0;
+
+        for (line, kind) in input.split_inclusive('\n').zip(classify_lines(input)) {
+            let text = line.trim_end_matches(['\n', '\r']);
+            let newline = &line[text.len()..];
+            let end = offset + text.chars().count();
+
+            match kind {
+                LineKind::FileHeader => {
+                    tokens.push(Token::new(TokenType::FileHeader, text, offset, end))
+                }
+                LineKind::Hunk => tokens.push(Token::new(TokenType::Hunk, text, offset, end)),
+                LineKind::Note => tokens.push(Token::new(TokenType::Comment, text, offset, end)),
+                LineKind::Other => tokens.push(Token::new(TokenType::Other, text, offset, end)),
+                LineKind::Inserted | LineKind::Deleted | LineKind::Context => {
+                    let token_type = match kind {
+                        LineKind::Inserted => TokenType::Inserted,
+                        LineKind::Deleted => TokenType::Deleted,
+                        _ => TokenType::Other,
+                    };
+                    match &self.inner {
+                        Some(inner) if !text.is_empty() => {
+                            // The marker is one ASCII char, so bytes and chars agree here
+                            let (marker, code) = text.split_at(1);
+                            tokens.push(Token::new(token_type, marker, offset, offset + 1));
+                            tokens.extend(inner.lex(code).into_iter().map(|mut token| {
+                                token.start += offset + 1;
+                                token.end += offset + 1;
+                                token
+                            }));
+                        }
+                        _ => tokens.push(Token::new(token_type, text, offset, end)),
+                    }
+                }
+            }
+            if !newline.is_empty() {
+                tokens.push(Token::new(
+                    TokenType::Newline,
+                    newline,
+                    end,
+                    end + newline.chars().count(),
+                ));
+            }
+            offset += line.chars().count();
+        }
+        tokens.retain(|token| !token.value.is_empty());
+        tokens
+    }
+}
+
+/// Whole-line class of each line of a diff: `inserted`, `deleted` or none
+pub fn line_classes(input: &str) -> Vec<&'static str> {
+    classify_lines(input)
+        .into_iter()
+        .map(|kind| match kind {
+            LineKind::Inserted => "inserted",
+            LineKind::Deleted => "deleted",
+            _ => "",
+        })
+        .collect()
+}
+
+/// Classify each line of a diff
+///
+/// `---` and `+++` are file headers only outside a hunk. How far a hunk goes is taken from
+/// the line counts in its header, so a removed line that starts with `--` is still a
+/// removal; a hunk header without counts lasts until the next header.
+fn classify_lines(input: &str) -> Vec<LineKind> {
+    const FILE_HEADERS: &[&str] = &[
+        "diff ",
+        "index ",
+        "new file mode",
+        "deleted file mode",
+        "old mode",
+        "new mode",
+        "similarity index",
+        "dissimilarity index",
+        "rename from",
+        "rename to",
+        "copy from",
+        "copy to",
+        "Binary files",
+    ];
+    // Old and new lines left in the current hunk
+    let mut remaining = (0usize, 0usize);
+
+    input
+        .split_inclusive('\n')
+        .map(|line| {
+            let line = line.trim_end_matches(['\n', '\r']);
+            let in_hunk = remaining != (0, 0);
+            if line.starts_with("@@") {
+                remaining = hunk_counts(line).unwrap_or((usize::MAX, usize::MAX));
+                LineKind::Hunk
+            } else if FILE_HEADERS.iter().any(|header| line.starts_with(header)) {
+                remaining = (0, 0);
+                LineKind::FileHeader
+            } else if !in_hunk && (line.starts_with("--- ") || line.starts_with("+++ ")) {
+                LineKind::FileHeader
+            } else if line.starts_with('+') {
+                remaining.1 = remaining.1.saturating_sub(1);
+                LineKind::Inserted
+            } else if line.starts_with('-') {
+                remaining.0 = remaining.0.saturating_sub(1);
+                LineKind::Deleted
+            } else if line.starts_with('\\') {
+                LineKind::Note
+            } else if line.starts_with(' ') || (in_hunk && line.is_empty()) {
+                remaining = (remaining.0.saturating_sub(1), remaining.1.saturating_sub(1));
+                LineKind::Context
+            } else {
+                LineKind::Other
+            }
+        })
+        .collect()
+}
+
+/// Old and new line counts from `@@ -12,5 +12,6 @@`, where a missing count means 1
+fn hunk_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.trim_start_matches('@').split_whitespace();
+    let count = |range: Option<&str>, sign: char| -> Option<usize> {
+        let range = range?.strip_prefix(sign)?;
+        match range.split_once(',') {
+            Some((_, count)) => count.parse().ok(),
+            None => range.parse::<usize>().ok().map(|_| 1),
+        }
+    };
+    let old = count(ranges.next(), '-')?;
+    let new = count(ranges.next(), '+')?;
+    Some((old, new))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LexerFactory, process_markdown_content};
+
+    #[test]
+    fn test_diff_lexer() {
+        let patch = "diff --git a/src/main.rs b/src/main.rs\n\
+                     --- a/src/main.rs\n\
+                     +++ b/src/main.rs\n\
+                     @@ -1,3 +1,3 @@ fn main() {\n\
+                     \x20fn main() {\n\
+                     ---x;\n\
+                     +    let x = 1;\n\
+                     \x20}\n";
+        let html = process_markdown_content(&format!("```diff-rust\n{patch}```\n"), None);
+        assert!(
+            html.contains("class=\"code-block-container has-lines\""),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<span class=\"line\"><span class=\"file-header\">--- a/src/main.rs</span>"
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<span class=\"line\"><span class=\"hunk\">@@ -1,3 +1,3 @@ fn main() {</span>"
+            ),
+            "{html}"
+        );
+        // Inside the hunk `---` is a removed line, not a file header
+        assert!(
+            html.contains("<span class=\"line deleted\"><span class=\"deleted\">-</span>"),
+            "{html}"
+        );
+        assert!(html.contains("<span class=\"line inserted\"><span class=\"inserted\">+</span>    <span class=\"keyword\">let</span>"), "{html}");
+        assert!(
+            html.contains("<span class=\"line\"> <span class=\"keyword\">fn</span>"),
+            "{html}"
+        );
+
+        // Without a language, added and removed lines are colored as a whole
+        let html = process_markdown_content("```patch\n-a\n+b\n```\n", None);
+        assert!(
+            html.contains("<span class=\"line deleted\"><span class=\"deleted\">-a</span>"),
+            "{html}"
+        );
+        assert_eq!(LexerFactory::find("diff-c").map(|l| l.name), Some("diff"));
+        assert!(LexerFactory::find("diff-go").is_none());
+
+        // An unknown inner language still gets whole-line colors
+        let html = process_markdown_content("```diff-go\n-a := 1\n+a := 2\n```\n", None);
+        assert!(
+            html.contains("class=\"code-block-container has-lines\""),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<span class=\"line deleted\"><span class=\"deleted\">-</span>a := 1\n</span>"
+            ),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<span class=\"line inserted\"><span class=\"inserted\">+</span>a := 2\n</span>"
+            ),
+            "{html}"
+        );
+    }
+}
@@ -0,0 +1,244 @@
+/// Options from a fenced code block's info string, e.g.
+/// `rust {3,7-9} linenos start=42 title="src/main.rs"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FenceInfo {
+    pub language: String,
+    /// File name or title shown in the header bar
+    pub title: Option<String>,
+    /// Short explanation shown in the header bar
+    pub caption: Option<String>,
+    /// Emphasized lines as inclusive ranges, counted from 1 at the top of the block
+    pub highlight: Vec<(usize, usize)>,
+    pub line_numbers: bool,
+    /// Number shown for the first line; setting it turns line numbers on
+    pub start: usize,
+}
+
+impl Default for FenceInfo {
+    fn default() -> Self {
+        Self {
+            language: String::new(),
+            title: None,
+            caption: None,
+            highlight: Vec::new(),
+            line_numbers: false,
+            start: 1,
+        }
+    }
+}
+
+impl FenceInfo {
+    /// Parse an info string; unknown options are ignored
+    pub fn parse(info: &str) -> Self {
+        let mut fence = Self::default();
+        for (i, part) in split_info(info).into_iter().enumerate() {
+            if let Some(ranges) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                fence
+                    .highlight
+                    .extend(ranges.split(',').filter_map(parse_range));
+            } else if part == "linenos" {
+                fence.line_numbers = true;
+            } else if let Some(start) = part.strip_prefix("start=") {
+                match start.parse() {
+                    Ok(start) => {
+                        fence.start = start;
+                        fence.line_numbers = true;
+                    }
+                    Err(_) => log::warn!("Invalid code block start line: {start:?}"),
+                }
+            } else if let Some(title) = part.strip_prefix("title=") {
+                fence.title = Some(unquote(title).to_string());
+            } else if let Some(caption) = part.strip_prefix("caption=") {
+                fence.caption = Some(unquote(caption).to_string());
+            } else if i == 0 && !part.contains('=') {
+                fence.language = part.to_string();
+            } else {
+                log::debug!("Ignoring code block option: {part:?}");
+            }
+        }
+        fence
+    }
+
+    /// Whether the block gets a header bar
+    pub fn has_header(&self) -> bool {
+        self.title.is_some() || self.caption.is_some()
+    }
+
+    /// Whether the block is rendered with per-line wrappers
+    pub fn has_line_options(&self) -> bool {
+        self.line_numbers || !self.highlight.is_empty()
+    }
+
+    fn is_highlighted(&self, line: usize) -> bool {
+        self.highlight
+            .iter()
+            .any(|&(from, to)| (from..=to).contains(&line))
+    }
+
+    /// Wrap each line of highlighted code in a `line` span, with a line number gutter if
+    /// enabled
+    ///
+    /// `line_classes` adds a class to each line, such as `inserted` in a diff. The number
+    /// is drawn by CSS from `data-line-number`, so it is never part of a text selection.
+    pub fn render_lines(&self, highlighted: &str, line_classes: &[&str]) -> String {
+        let mut out = String::with_capacity(highlighted.len() * 2);
+        for (i, line) in split_lines(highlighted).iter().enumerate() {
+            let mut class = String::from("line");
+            if self.is_highlighted(i + 1) {
+                class.push_str(" highlighted");
+            }
+            if let Some(&extra) = line_classes.get(i)
+                && !extra.is_empty()
+            {
+                class.push(' ');
+                class.push_str(extra);
+            }
+            out.push_str(&format!("<span class=\"{class}\">"));
+            if self.line_numbers {
+                out.push_str(&format!(
+                    "<span class=\"line-number\" data-line-number=\"{}\" aria-hidden=\"true\"></span>",
+                    self.start + i
+                ));
+            }
+            out.push_str(line);
+            out.push_str("\n</span>");
+        }
+        out
+    }
+}
+
+/// Split an info string on whitespace, keeping `{...}` groups and quoted values together
+fn split_info(info: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut in_braces = false;
+    let mut quote = None;
+    for (i, c) in info.char_indices() {
+        match c {
+            c if quote == Some(c) => quote = None,
+            _ if quote.is_some() => {}
+            '"' | '\'' => quote = Some(c),
+            '{' => in_braces = true,
+            '}' => in_braces = false,
+            c if c.is_whitespace() && !in_braces => {
+                if let Some(from) = start.take() {
+                    parts.push(&info[from..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(from) = start {
+        parts.push(&info[from..]);
+    }
+    parts
+}
+
+/// Strip matching quotes around an option value
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .into_iter()
+        .find_map(|quote| value.strip_prefix(quote)?.strip_suffix(quote))
+        .unwrap_or(value)
+}
+
+/// `7-9` or `3`
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let range = range.trim();
+    let (from, to) = range.split_once('-').unwrap_or((range, range));
+    let (from, to) = (from.trim().parse().ok()?, to.trim().parse().ok()?);
+    (from <= to).then_some((from, to))
+}
+
+/// Split highlighted HTML into lines, closing spans that are open at a line break and
+/// reopening them on the next line
+///
+/// A trailing newline does not start another line.
+fn split_lines(html: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut open: Vec<&str> = Vec::new();
+    // Whether the current line has any text, as opposed to just reopened tags
+    let mut has_text = false;
+    let mut rest = html;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            let tag = &rest[..end];
+            if tag.starts_with("</") {
+                open.pop();
+            } else {
+                open.push(tag);
+            }
+            line.push_str(tag);
+            rest = &rest[end..];
+            continue;
+        }
+        if c == '\n' {
+            line.push_str(&"</span>".repeat(open.len()));
+            lines.push(std::mem::take(&mut line));
+            line.extend(open.iter().copied());
+            has_text = false;
+        } else {
+            line.push(c);
+            has_text = true;
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    if has_text {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fence_info_and_lines() {
+        let fence = FenceInfo::parse(
+            "rust {3, 7-9} linenos start=42 title=\"/etc/apt/sources.list\" caption='Say \"hi\"' x=1",
+        );
+        assert_eq!(fence.language, "rust");
+        assert_eq!(fence.title.as_deref(), Some("/etc/apt/sources.list"));
+        assert_eq!(fence.caption.as_deref(), Some("Say \"hi\""));
+        assert_eq!(fence.highlight, vec![(3, 3), (7, 9)]);
+        assert!(fence.line_numbers);
+        assert_eq!(fence.start, 42);
+        assert_eq!(FenceInfo::parse("{2}").language, "");
+
+        let html = "<span class=\"comment\">/* a\nb */</span> x<span>\n</span>";
+        let fence = FenceInfo::parse("c {2} start=9");
+        assert_eq!(
+            fence.render_lines(html, &[]),
+            "<span class=\"line\"><span class=\"line-number\" data-line-number=\"9\" aria-hidden=\"true\"></span>\
+             <span class=\"comment\">/* a</span>\n</span>\
+             <span class=\"line highlighted\"><span class=\"line-number\" data-line-number=\"10\" aria-hidden=\"true\"></span>\
+             <span class=\"comment\">b */</span> x<span></span>\n</span>"
+        );
+
+        let html = super::super::process_markdown_content(
+            "```rust {2} linenos\nlet a = 1;\nlet b = 2;\n```\n",
+            None,
+        );
+        assert!(
+            html.contains("class=\"code-block-container has-lines has-line-numbers\""),
+            "{html}"
+        );
+        assert!(html.contains("<span class=\"line highlighted\"><span class=\"line-number\" data-line-number=\"2\""), "{html}");
+        assert!(!html.contains("data-fence"), "{html}");
+
+        // Code is escaped once, so an entity in the source shows up literally
+        let html = super::super::process_markdown_content("```\na < b && \"&lt;\"\n```\n", None);
+        assert!(html.contains("a &lt; b &amp;&amp; \"&amp;lt;\""), "{html}");
+
+        let html = super::super::process_markdown_content("```text title=\"<b>\"\nx\n```\n", None);
+        assert!(
+            html.contains("<span class=\"code-block-title\">&lt;b&gt;</span>"),
+            "{html}"
+        );
+    }
+}
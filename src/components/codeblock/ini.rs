@@ -0,0 +1,125 @@
+use super::LanguageLexer;
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+use super::scalar_type;
+
+/// INI and conf files: sections, `key = value` and `key: value` pairs, comments
+pub struct IniLexer;
+
+impl LanguageLexer for IniLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &["true", "false", "yes", "no", "on", "off", "True", "False", "TRUE", "FALSE"]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let literals = self.get_keywords();
+        let mut lexer = BaseLexer::new(input);
+
+        while lexer.position < lexer.input.len() {
+            lexer.consume_whitespace();
+            match lexer.current_char() {
+                None => break,
+                Some('\n' | '\r') => {
+                    lexer.consume_newlines();
+                }
+                Some(prefix @ (';' | '#')) => {
+                    lexer.consume_single_line_comment(&prefix.to_string());
+                }
+                Some('[') => {
+                    let start = lexer.position;
+                    let mut value = lexer.take_while(|c| c != ']' && c != '\n' && c != '\r');
+                    if lexer.current_char() == Some(']') {
+                        value.push(']');
+                        lexer.advance();
+                    }
+                    lexer.add_token(TokenType::Type, value, start, lexer.position);
+                    lex_rest_of_line(&mut lexer, literals);
+                }
+                Some(_) => {
+                    let start = lexer.position;
+                    let key = lexer.take_while(|c| !matches!(c, '=' | ':' | '\n' | '\r'));
+                    add_trimmed(&mut lexer, key, start, TokenType::Property);
+                    if let Some(separator @ ('=' | ':')) = lexer.current_char() {
+                        lexer.add_operator(separator.to_string());
+                        lex_rest_of_line(&mut lexer, literals);
+                    }
+                }
+            }
+        }
+        lexer.get_tokens()
+    }
+}
+
+/// Lex a value and any trailing comment up to the end of the line
+fn lex_rest_of_line(lexer: &mut BaseLexer, literals: &[&str]) {
+    loop {
+        match lexer.current_char() {
+            None | Some('\n' | '\r') => return,
+            Some(' ' | '\t') => {
+                lexer.consume_whitespace();
+            }
+            Some(prefix @ (';' | '#')) => {
+                lexer.consume_single_line_comment(&prefix.to_string());
+                return;
+            }
+            Some(quote @ ('"' | '\'')) => {
+                lexer.consume_string(quote);
+            }
+            Some(_) => {
+                // Up to the end of the line or a comment after whitespace
+                let start = lexer.position;
+                let mut value = String::new();
+                while let Some(c) = lexer.current_char() {
+                    let comment = matches!(c, ';' | '#') && value.ends_with(char::is_whitespace);
+                    if c == '\n' || c == '\r' || comment {
+                        break;
+                    }
+                    value.push(c);
+                    lexer.advance();
+                }
+                let token_type = scalar_type(value.trim_end(), literals);
+                add_trimmed(lexer, value, start, token_type);
+            }
+        }
+    }
+}
+
+/// Add a token without its trailing whitespace, which becomes a token of its own
+fn add_trimmed(lexer: &mut BaseLexer, value: String, start: usize, token_type: TokenType) {
+    let trimmed = value.trim_end();
+    let rest = value[trimmed.len()..].to_string();
+    if !trimmed.is_empty() {
+        lexer.add_token(token_type, trimmed.to_string(), start, lexer.position);
+    }
+    if !rest.is_empty() {
+        lexer.add_token(TokenType::Whitespace, rest, start, lexer.position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_ini_lexer() {
+        let tokens = significant_tokens("conf", "[core]\neditor = vim ; c\nbare = yes\n");
+        let tokens: Vec<_> = tokens
+            .iter()
+            .map(|(t, v)| (t.clone(), v.as_str()))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Type, "[core]"),
+                (TokenType::Property, "editor"),
+                (TokenType::Operator, "="),
+                (TokenType::String, "vim"),
+                (TokenType::Comment, "; c"),
+                (TokenType::Property, "bare"),
+                (TokenType::Operator, "="),
+                (TokenType::Keyword, "yes"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::LanguageLexer;
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+
+/// JSON and JSON5: keys, strings, numbers, literals and JSON5 comments
+pub struct JsonLexer;
+
+impl LanguageLexer for JsonLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &["true", "false", "null"]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let keywords = self.get_keywords();
+        let numbers = ["Infinity", "NaN"];
+        let mut lexer = BaseLexer::new(input);
+
+        while lexer.position < lexer.input.len() {
+            let ch = lexer.current_char().unwrap_or('\0');
+            match ch {
+                ' ' | '\t' => {
+                    lexer.consume_whitespace();
+                }
+                '\n' | '\r' => {
+                    lexer.consume_newlines();
+                }
+                '/' if lexer.peek_char(1) == Some('/') => {
+                    lexer.consume_single_line_comment("//");
+                }
+                '/' if lexer.peek_char(1) == Some('*') => {
+                    lexer.consume_multi_line_comment("/*", "*/");
+                }
+                '"' | '\'' => {
+                    lexer.consume_string(ch);
+                    if lexer.next_on_line_is(&[':']) {
+                        lexer.retype_last_token(TokenType::Property);
+                    }
+                }
+                '0'..='9' => {
+                    lexer.consume_number();
+                }
+                '.' if lexer.peek_char(1).is_some_and(|c| c.is_ascii_digit()) => {
+                    lexer.consume_number();
+                }
+                'a'..='z' | 'A'..='Z' | '_' | '$' => {
+                    let start = lexer.position;
+                    let value =
+                        lexer.take_while(|c| c.is_alphanumeric() || c == '_' || c == '$');
+                    let token_type = if lexer.next_on_line_is(&[':']) {
+                        // Unquoted JSON5 key
+                        TokenType::Property
+                    } else if keywords.contains(&value.as_str()) {
+                        TokenType::Keyword
+                    } else if numbers.contains(&value.as_str()) {
+                        TokenType::Number
+                    } else {
+                        TokenType::Identifier
+                    };
+                    lexer.add_token(token_type, value, start, lexer.position);
+                }
+                '{' | '}' | '[' | ']' | ':' | ',' | '-' | '+' => {
+                    lexer.add_operator(ch.to_string());
+                }
+                _ => {
+                    lexer.add_other(ch.to_string());
+                }
+            }
+        }
+        lexer.get_tokens()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_json_lexer() {
+        let tokens = significant_tokens("json5", "{\"a\": [1, true, null], b: 'x' /* c */}");
+        let tokens: Vec<_> = tokens
+            .iter()
+            .map(|(t, v)| (t.clone(), v.as_str()))
+            .collect();
+        // JSON5 allows bare keys, single quotes and comments
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Operator, "{"),
+                (TokenType::Property, "\"a\""),
+                (TokenType::Operator, ":"),
+                (TokenType::Operator, "["),
+                (TokenType::Number, "1"),
+                (TokenType::Operator, ","),
+                (TokenType::Keyword, "true"),
+                (TokenType::Operator, ","),
+                (TokenType::Keyword, "null"),
+                (TokenType::Operator, "]"),
+                (TokenType::Operator, ","),
+                (TokenType::Property, "b"),
+                (TokenType::Operator, ":"),
+                (TokenType::String, "'x'"),
+                (TokenType::Comment, "/* c */"),
+                (TokenType::Operator, "}"),
+            ]
+        );
+    }
+}
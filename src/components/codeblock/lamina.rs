@@ -1,140 +1,56 @@
 use super::LanguageLexer;
-use super::{TokenType, Token};
-use super::BaseLexer;
+use super::Token;
+use super::ssa::{SsaDialect, lex_ssa};
 
 pub struct LaminaLexer;
 
+const LAMINA: SsaDialect = SsaDialect {
+    keywords: &[
+        // Function and control flow keywords
+        "fn", "ret", "br", "call", "if", "else", "loop", "while", "for",
+        
+        // Memory operations
+        "alloc", "dealloc", "load", "store", "stack", "heap",
+        
+        // Type operations
+        "getfield", "getelem",
+        
+        // Binary operators
+        "add", "sub", "mul", "div", "mod", "and", "or", "xor", "shl", "shr",
+        "eq", "ne", "lt", "le", "gt", "ge",
+        
+        // Unary operators
+        "not", "neg",
+        
+        // Annotations
+        "export", "import",
+        
+        // Special operations
+        "print", "global", "type",
+    ],
+    types: &[
+        "u8", "u16", "u32", "u64", "u128", "f32", "f64", "bool", "ptr", "struct", "array",
+        "void",
+    ],
+    attributes: &[],
+    line_comment: "#",
+    metadata: false,
+};
+
 impl LanguageLexer for LaminaLexer {
     fn get_keywords(&self) -> &[&str] {
-        &[
-            // Function and control flow keywords
-            "fn", "ret", "br", "call", "if", "else", "loop", "while", "for",
-            
-            // Memory operations
-            "alloc", "dealloc", "load", "store", "stack", "heap",
-            
-            // Type operations
-            "getfield", "getelem", "ptr",
-            
-            // Binary operators
-            "add", "sub", "mul", "div", "mod", "and", "or", "xor", "shl", "shr",
-            "eq", "ne", "lt", "le", "gt", "ge",
-            
-            // Unary operators
-            "not", "neg",
-            
-            // Type keywords
-            "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128",
-            "f32", "f64", "bool", "ptr", "struct", "array",
-            
-            // Annotations
-            "export", "import",
-            
-            // Special operations
-            "print", "global", "type",
-        ]
+        LAMINA.keywords
     }
 
     fn lex(&self, input: &str) -> Vec<Token> {
-        let keywords = self.get_keywords();
-        let mut lexer = BaseLexer::new(input);
-        
-        while lexer.position < lexer.input.len() {
-            let ch = lexer.current_char().unwrap_or('\0');
-            match ch {
-                ' ' | '\t' => {
-                    lexer.consume_whitespace();
-                }
-                '\n' | '\r' => {
-                    lexer.consume_newlines();
-                }
-                '#' => {
-                    // Comments in Lamina IR
-                    lexer.consume_single_line_comment("#");
-                }
-                '"' => {
-                    // String literals
-                    lexer.consume_string('"');
-                }
-                '@' => {
-                    // Global symbols and function names
-                    let start = lexer.position;
-                    let mut value = String::from("@");
-                    lexer.advance();
-                    
-                    // Consume identifier after @
-                    while let Some(c) = lexer.current_char() {
-                        if c.is_alphanumeric() || c == '_' || c == '-' {
-                            value.push(c);
-                            lexer.advance();
-                        } else {
-                            break;
-                        }
-                    }
-                    
-                    lexer.add_token(TokenType::Identifier, value, start, lexer.position);
-                }
-                '%' => {
-                    // Local variables and SSA values
-                    let start = lexer.position;
-                    let mut value = String::from("%");
-                    lexer.advance();
-                    
-                    // Consume identifier after %
-                    while let Some(c) = lexer.current_char() {
-                        if c.is_alphanumeric() || c == '_' {
-                            value.push(c);
-                            lexer.advance();
-                        } else {
-                            break;
-                        }
-                    }
-                    
-                    lexer.add_token(TokenType::Variable, value, start, lexer.position);
-                }
-                'a'..='z' | 'A'..='Z' | '_' => {
-                    // Identifiers, keywords, and operators
-                    let start = lexer.position;
-                    let mut value = String::new();
-                    
-                    while let Some(c) = lexer.current_char() {
-                        if c.is_alphanumeric() || c == '_' || c == '.' {
-                            value.push(c);
-                            lexer.advance();
-                        } else {
-                            break;
-                        }
-                    }
-                    
-                    // Check if it's a keyword
-                    if keywords.contains(&value.as_str()) {
-                        lexer.add_token(TokenType::Keyword, value, start, lexer.position);
-                    } else {
-                        lexer.add_token(TokenType::Identifier, value, start, lexer.position);
-                    }
-                }
-                '0'..='9' => {
-                    // Numbers
-                    lexer.consume_number();
-                }
-                '<' | '>' | '=' | '+' | '-' | '*' | '&' | '|' | '!' | '?' | ':' | ';' | ','
-                | '.' | '(' | ')' | '[' | ']' | '{' | '}' => {
-                    // Operators and punctuation
-                    lexer.add_operator(ch.to_string());
-                }
-                _ => {
-                    lexer.add_other(ch.to_string());
-                }
-            }
-        }
-        
-        lexer.get_tokens()
+        lex_ssa(input, &LAMINA)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::TokenType;
 
     #[test]
     fn test_lamina_lexer_basic() {
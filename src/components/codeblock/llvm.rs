@@ -0,0 +1,137 @@
+use super::LanguageLexer;
+use super::Token;
+use super::ssa::{SsaDialect, lex_ssa};
+
+pub struct LlvmIrLexer;
+
+const LLVM: SsaDialect = SsaDialect {
+    keywords: &[
+        // Module structure
+        "define", "declare", "global", "constant", "type", "attributes", "source_filename",
+        "target", "datalayout", "triple", "section", "to", "x",
+
+        // Terminators
+        "ret", "br", "switch", "indirectbr", "invoke", "resume", "unreachable",
+
+        // Arithmetic and bitwise operations
+        "fneg", "add", "fadd", "sub", "fsub", "mul", "fmul", "udiv", "sdiv", "fdiv", "urem",
+        "srem", "frem", "shl", "lshr", "ashr", "and", "or", "xor",
+
+        // Vector and aggregate operations
+        "extractelement", "insertelement", "shufflevector", "extractvalue", "insertvalue",
+
+        // Memory operations
+        "alloca", "load", "store", "fence", "cmpxchg", "atomicrmw", "getelementptr",
+
+        // Conversions
+        "trunc", "zext", "sext", "fptrunc", "fpext", "fptoui", "fptosi", "uitofp", "sitofp",
+        "ptrtoint", "inttoptr", "bitcast", "addrspacecast",
+
+        // Other operations
+        "icmp", "fcmp", "phi", "select", "call", "tail", "musttail", "freeze", "va_arg",
+        "landingpad",
+
+        // Comparison predicates
+        "eq", "ne", "ugt", "uge", "ult", "ule", "sgt", "sge", "slt", "sle", "oeq", "ogt",
+        "oge", "olt", "ole", "one", "ord", "ueq", "une", "uno",
+
+        // Constants
+        "null", "undef", "poison", "zeroinitializer", "true", "false",
+    ],
+    types: &[
+        "void", "ptr", "half", "bfloat", "float", "double", "fp128", "x86_fp80", "label",
+        "metadata", "token", "opaque",
+    ],
+    attributes: &[
+        // Linkage and visibility
+        "private", "internal", "external", "linkonce", "linkonce_odr", "weak", "weak_odr",
+        "common", "appending", "dso_local", "unnamed_addr", "local_unnamed_addr", "hidden",
+
+        // Parameter and function attributes
+        "noundef", "nonnull", "nocapture", "readonly", "readnone", "writeonly", "signext",
+        "zeroext", "byval", "sret", "noalias", "immarg", "nounwind", "noinline",
+        "alwaysinline", "optnone", "uwtable", "mustprogress", "noreturn", "norecurse",
+        "willreturn", "nofree", "nosync", "memory",
+
+        // Instruction flags
+        "nsw", "nuw", "exact", "inbounds", "align", "volatile", "atomic", "fast", "nnan",
+        "ninf",
+    ],
+    line_comment: ";",
+    metadata: true,
+};
+
+impl LanguageLexer for LlvmIrLexer {
+    fn get_keywords(&self) -> &[&str] {
+        LLVM.keywords
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        lex_ssa(input, &LLVM)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_llvm_ir_lexer() {
+        let lexer = LlvmIrLexer;
+        let input = r#"@.str = private unnamed_addr constant [6 x i8] c"hello\00", align 1
+
+; Function Attrs: nounwind
+define dso_local i32 @add(i32 noundef %a, i32 noundef %b) #0 {
+entry:
+  %sum = add nsw i32 %a, %b, !dbg !12
+  br label %for.body
+
+for.body:
+  ret i32 %sum
+}
+
+!0 = !{!"clang version 17"}"#;
+
+        let tokens = lexer.lex(input);
+        let of_type = |token_type: TokenType| -> Vec<&str> {
+            tokens
+                .iter()
+                .filter(|t| t.token_type == token_type)
+                .map(|t| t.value.as_str())
+                .collect()
+        };
+
+        assert_eq!(of_type(TokenType::Comment), vec!["; Function Attrs: nounwind"]);
+        assert_eq!(of_type(TokenType::Function), vec!["entry", "for.body"]);
+        assert_eq!(of_type(TokenType::Identifier), vec!["@.str", "@add"]);
+        assert_eq!(of_type(TokenType::String), vec!["c\"hello\\00\""]);
+        assert!(of_type(TokenType::Variable).contains(&"%for.body"));
+        assert!(of_type(TokenType::Keyword).contains(&"define"));
+        assert!(of_type(TokenType::Type).contains(&"i32"));
+        assert!(of_type(TokenType::Type).contains(&"label"));
+        assert!(of_type(TokenType::Macro).contains(&"#0"));
+        assert!(of_type(TokenType::Macro).contains(&"!dbg"));
+        assert!(of_type(TokenType::Macro).contains(&"nsw"));
+        assert!(of_type(TokenType::Macro).contains(&"!\"clang version 17\""));
+
+        // Lamina shares the lexer, so the same constructs look the same
+        let tokens = significant_tokens("lamina", "entry:\n  %sum = add.i32 %a, %b");
+        let kinds: Vec<_> = tokens.iter().map(|(t, v)| (t.clone(), v.as_str())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (TokenType::Function, "entry"),
+                (TokenType::Operator, ":"),
+                (TokenType::Variable, "%sum"),
+                (TokenType::Operator, "="),
+                (TokenType::Keyword, "add"),
+                (TokenType::Operator, "."),
+                (TokenType::Type, "i32"),
+                (TokenType::Variable, "%a"),
+                (TokenType::Operator, ","),
+                (TokenType::Variable, "%b"),
+            ]
+        );
+    }
+}
@@ -3,10 +3,21 @@ mod javascript;
 mod python;
 mod css;
 mod bash;
+mod console;
+mod diff;
 mod html;
 mod c;
 mod cpp;
 mod lamina;
+mod llvm;
+mod ssa;
+mod json;
+mod toml;
+mod yaml;
+mod ini;
+mod asm;
+mod plain;
+mod fence;
 
 use html::HTMLLexer;
 use javascript::JavaScriptLexer;
@@ -14,11 +25,25 @@ use python::PythonLexer;
 use rust::RustLexer;
 use css::CSSLexer;
 use bash::BashLexer;
+use console::ConsoleLexer;
+use diff::DiffLexer;
 use c::CLexer;
 use cpp::CppLexer;
 use lamina::LaminaLexer;
+use llvm::LlvmIrLexer;
+use json::JsonLexer;
+use toml::TomlLexer;
+use yaml::YamlLexer;
+use ini::IniLexer;
+use asm::{Arm64AsmLexer, RiscVAsmLexer, X86AsmLexer};
+use plain::PlainTextLexer;
+use fence::FenceInfo;
 use log::debug;
 
+use crate::components::math::render_math;
+use crate::escape::{escape_attr, escape_text};
+use crate::sanitize::{Allowlist, Sanitizer, is_safe_url};
+
 /// Code block component for syntax highlighting and HTML generation
 pub struct CodeBlock {
     pub language: String,
@@ -46,10 +71,24 @@ pub enum TokenType {
     Command,
     Shebang,
     Function,
+
+    // Data formats
+    Property,
+
+    // Console sessions
+    Prompt,
+    Output,
+
+    // Diffs
+    Inserted,
+    Deleted,
+    Hunk,
+    FileHeader,
 }
 
 /// Token structure
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct Token {
     pub token_type: TokenType,
     pub value: String,
@@ -57,6 +96,18 @@ pub struct Token {
     pub end: usize,
 }
 
+impl Token {
+    /// A token spanning `start..end`, counted in chars like every lexer's positions
+    pub fn new(token_type: TokenType, value: impl Into<String>, start: usize, end: usize) -> Self {
+        Self {
+            token_type,
+            value: value.into(),
+            start,
+            end,
+        }
+    }
+}
+
 /// Language-specific lexer trait for extensibility
 pub trait LanguageLexer {
     fn lex(&self, input: &str) -> Vec<Token>;
@@ -87,33 +138,12 @@ impl BaseLexer {
         self.input.get(self.position + offset).copied()
     }
 
-    pub fn peek_multiple(&self, count: usize) -> Option<String> {
-        if self.position + count <= self.input.len() {
-            Some(self.input[self.position..self.position + count].iter().collect())
-        } else {
-            None
-        }
-    }
-
-    pub fn peek_multiple_back(&self, count: usize) -> Option<String> {
-        if self.position >= count {
-            Some(self.input[self.position - count + 1..=self.position].iter().collect())
-        } else {
-            None
-        }
-    }
-
     pub fn advance(&mut self) {
         self.position += 1;
     }
 
     pub fn add_token(&mut self, token_type: TokenType, value: String, start: usize, end: usize) {
-        self.tokens.push(Token {
-            token_type,
-            value,
-            start,
-            end,
-        });
+        self.tokens.push(Token::new(token_type, value, start, end));
     }
 
     pub fn consume_whitespace(&mut self) -> Option<String> {
@@ -158,6 +188,7 @@ impl BaseLexer {
         }
     }
 
+    #[allow(dead_code)]
     pub fn consume_identifier(&mut self, keywords: &[&str]) -> Option<String> {
         let start = self.position;
         let mut value = String::new();
@@ -256,7 +287,7 @@ impl BaseLexer {
                             value.push(next_c);
                             self.advance();
                             while let Some(c) = self.current_char() {
-                                if (c >= '0' && c <= '7') || c == '_' {
+                                if ('0'..='7').contains(&c) || c == '_' {
                                     value.push(c);
                                     self.advance();
                                 } else {
@@ -282,27 +313,27 @@ impl BaseLexer {
                             }
                             
                             // Check for scientific notation
-                            if let Some(c) = self.current_char() {
-                                if c == 'e' || c == 'E' {
-                                    value.push(c);
+                            if let Some(c) = self.current_char()
+                                && (c == 'e' || c == 'E')
+                            {
+                                value.push(c);
+                                self.advance();
+
+                                // Optional sign
+                                if let Some(sign) = self.current_char()
+                                    && (sign == '+' || sign == '-')
+                                {
+                                    value.push(sign);
                                     self.advance();
-                                    
-                                    // Optional sign
-                                    if let Some(sign) = self.current_char() {
-                                        if sign == '+' || sign == '-' {
-                                            value.push(sign);
-                                            self.advance();
-                                        }
-                                    }
-                                    
-                                    // Exponent digits
-                                    while let Some(c) = self.current_char() {
-                                        if c.is_ascii_digit() || c == '_' {
-                                            value.push(c);
-                                            self.advance();
-                                        } else {
-                                            break;
-                                        }
+                                }
+
+                                // Exponent digits
+                                while let Some(c) = self.current_char() {
+                                    if c.is_ascii_digit() || c == '_' {
+                                        value.push(c);
+                                        self.advance();
+                                    } else {
+                                        break;
                                     }
                                 }
                             }
@@ -330,27 +361,27 @@ impl BaseLexer {
                 }
                 
                 // Check for scientific notation
-                if let Some(c) = self.current_char() {
-                    if c == 'e' || c == 'E' {
-                        value.push(c);
+                if let Some(c) = self.current_char()
+                    && (c == 'e' || c == 'E')
+                {
+                    value.push(c);
+                    self.advance();
+
+                    // Optional sign
+                    if let Some(sign) = self.current_char()
+                        && (sign == '+' || sign == '-')
+                    {
+                        value.push(sign);
                         self.advance();
-                        
-                        // Optional sign
-                        if let Some(sign) = self.current_char() {
-                            if sign == '+' || sign == '-' {
-                                value.push(sign);
-                                self.advance();
-                            }
-                        }
-                        
-                        // Exponent digits
-                        while let Some(c) = self.current_char() {
-                            if c.is_ascii_digit() || c == '_' {
-                                value.push(c);
-                                self.advance();
-                            } else {
-                                break;
-                            }
+                    }
+
+                    // Exponent digits
+                    while let Some(c) = self.current_char() {
+                        if c.is_ascii_digit() || c == '_' {
+                            value.push(c);
+                            self.advance();
+                        } else {
+                            break;
                         }
                     }
                 }
@@ -427,25 +458,19 @@ impl BaseLexer {
             value.push(c);
             self.advance();
 
-            // Check for nested comment start
-            if start_prefix.len() > 0 && c == start_prefix.chars().next().unwrap() {
-                if let Some(next_chars) = self.peek_multiple(start_prefix.len()) {
-                    if next_chars == start_prefix {
-                        depth += 1;
-                    }
-                }
+            // The opening delimiter can't also close the comment, as in `/*/`
+            if value.len() < start_prefix.len() + end_suffix.len() {
+                continue;
             }
 
-            // Check for end suffix
-            if c == end_suffix.chars().last().unwrap() {
-                if let Some(prev_chars) = self.peek_multiple_back(end_suffix.len()) {
-                    if prev_chars == end_suffix {
-                        depth -= 1;
-                        if depth == 0 {
-                            break;
-                        }
-                    }
+            if value.ends_with(end_suffix) {
+                depth -= 1;
+                if depth == 0 {
+                    break;
                 }
+            } else if !start_prefix.is_empty() && value.ends_with(start_prefix) {
+                // Nested comment start
+                depth += 1;
             }
         }
 
@@ -453,6 +478,34 @@ impl BaseLexer {
         Some(value)
     }
 
+    /// Advance over characters matching `pred`, returning them without adding a token
+    pub fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.current_char() {
+            if !pred(c) {
+                break;
+            }
+            value.push(c);
+            self.advance();
+        }
+        value
+    }
+
+    /// Whether the next character on this line, after spaces and tabs, is one of `expected`
+    pub fn next_on_line_is(&self, expected: &[char]) -> bool {
+        self.input[self.position..]
+            .iter()
+            .find(|&&c| c != ' ' && c != '\t')
+            .is_some_and(|c| expected.contains(c))
+    }
+
+    /// Change the type of the last token, e.g. once a string turns out to be a key
+    pub fn retype_last_token(&mut self, token_type: TokenType) {
+        if let Some(token) = self.tokens.last_mut() {
+            token.token_type = token_type;
+        }
+    }
+
     pub fn add_operator(&mut self, value: String) {
         let start = self.position;
         self.add_token(TokenType::Operator, value, start, self.position + 1);
@@ -470,23 +523,190 @@ impl BaseLexer {
     }
 }
 
+/// A highlighted language and the names it is found by
+pub struct Language {
+    /// Canonical name
+    pub name: &'static str,
+    /// Other names accepted in code fences
+    pub aliases: &'static [&'static str],
+    /// File extensions, without the dot
+    pub extensions: &'static [&'static str],
+    create: fn() -> Box<dyn LanguageLexer>,
+}
+
+impl Language {
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.aliases.contains(&name) || self.extensions.contains(&name)
+    }
+}
+
+/// Every language with a lexer; add new lexers here
+static LANGUAGES: &[Language] = &[
+    Language {
+        name: "rust",
+        aliases: &[],
+        extensions: &["rs"],
+        create: || Box::new(RustLexer),
+    },
+    Language {
+        name: "javascript",
+        aliases: &["node"],
+        extensions: &["js", "mjs", "cjs"],
+        create: || Box::new(JavaScriptLexer),
+    },
+    Language {
+        name: "c",
+        aliases: &[],
+        extensions: &["c", "h"],
+        create: || Box::new(CLexer),
+    },
+    Language {
+        name: "cpp",
+        aliases: &["c++"],
+        extensions: &["cpp", "cc", "cxx", "hpp", "hh", "hxx"],
+        create: || Box::new(CppLexer),
+    },
+    Language {
+        name: "python",
+        aliases: &["python3"],
+        extensions: &["py"],
+        create: || Box::new(PythonLexer),
+    },
+    Language {
+        name: "css",
+        aliases: &[],
+        extensions: &["css"],
+        create: || Box::new(CSSLexer),
+    },
+    Language {
+        name: "bash",
+        aliases: &["shell", "zsh"],
+        extensions: &["sh", "bash"],
+        create: || Box::new(BashLexer),
+    },
+    Language {
+        name: "console",
+        aliases: &["shell-session", "sh-session", "terminal"],
+        extensions: &[],
+        create: || Box::new(ConsoleLexer),
+    },
+    Language {
+        name: "diff",
+        aliases: &["patch", "udiff"],
+        extensions: &["diff", "patch"],
+        create: || Box::new(DiffLexer::new(None)),
+    },
+    Language {
+        name: "html",
+        aliases: &["xhtml"],
+        extensions: &["html", "htm"],
+        create: || Box::new(HTMLLexer),
+    },
+    Language {
+        name: "lamina",
+        aliases: &["lamina-ir"],
+        extensions: &["lamina"],
+        create: || Box::new(LaminaLexer),
+    },
+    Language {
+        name: "llvm",
+        aliases: &["llvm-ir", "llvmir"],
+        extensions: &["ll"],
+        create: || Box::new(LlvmIrLexer),
+    },
+    Language {
+        name: "json",
+        aliases: &["json5", "jsonc"],
+        extensions: &["json", "json5"],
+        create: || Box::new(JsonLexer),
+    },
+    Language {
+        name: "toml",
+        aliases: &[],
+        extensions: &["toml"],
+        create: || Box::new(TomlLexer),
+    },
+    Language {
+        name: "yaml",
+        aliases: &["yml"],
+        extensions: &["yaml", "yml"],
+        create: || Box::new(YamlLexer),
+    },
+    Language {
+        name: "ini",
+        aliases: &["conf", "cfg", "dosini"],
+        extensions: &["ini", "conf", "cfg"],
+        create: || Box::new(IniLexer),
+    },
+    Language {
+        name: "x86asm",
+        aliases: &["asm", "nasm", "gas", "x86", "x86_64", "x86-64"],
+        extensions: &["asm", "s", "nasm"],
+        create: || Box::new(X86AsmLexer),
+    },
+    Language {
+        name: "arm64",
+        aliases: &["aarch64", "arm64asm"],
+        extensions: &[],
+        create: || Box::new(Arm64AsmLexer),
+    },
+    Language {
+        name: "riscv",
+        aliases: &["riscv64", "riscv32", "rv64", "rv32", "riscvasm"],
+        extensions: &[],
+        create: || Box::new(RiscVAsmLexer),
+    },
+    Language {
+        name: "text",
+        aliases: &["plain", "plaintext"],
+        extensions: &["txt"],
+        create: || Box::new(PlainTextLexer),
+    },
+];
+
 pub struct LexerFactory;
 
 impl LexerFactory {
+    /// Lexer for a fence language, falling back to plain text for unknown ones
+    ///
+    /// `diff-<language>` is a diff whose lines are highlighted as `<language>`.
     pub fn create_lexer(language: &str) -> Box<dyn LanguageLexer> {
-        match language.to_lowercase().as_str() {
-            "rust" | "rs" => Box::new(RustLexer),
-            "javascript" | "js" => Box::new(JavaScriptLexer),
-            "c" => Box::new(CLexer),
-            "cpp" | "c++" => Box::new(CppLexer),
-            "python" | "py" => Box::new(PythonLexer),
-            "css" => Box::new(CSSLexer),
-            "bash" | "shell" | "sh" => Box::new(BashLexer),
-            "html" => Box::new(HTMLLexer),
-            "lamina" | "lamina-ir" => Box::new(LaminaLexer),
-            _ => Box::new(JavaScriptLexer), // Default fallback
+        if let Some(inner) = language.trim().to_lowercase().strip_prefix("diff-") {
+            return Box::new(DiffLexer::new(Some(Self::create_lexer(inner))));
+        }
+        match Self::find(language) {
+            Some(language) => (language.create)(),
+            None => {
+                debug!("Unknown code block language '{language}', highlighting as plain text");
+                Box::new(PlainTextLexer)
+            }
         }
     }
+
+    /// Look up a language by name, alias or file extension, ignoring case
+    ///
+    /// A file name such as `Cargo.toml` or `src/main.rs` is looked up by its extension,
+    /// and `diff-<language>` is a diff if `<language>` is known.
+    pub fn find(name: &str) -> Option<&'static Language> {
+        let name = name.trim().to_lowercase();
+        if let Some(inner) = name.strip_prefix("diff-") {
+            return Self::find(inner).and(Self::find("diff"));
+        }
+        LANGUAGES
+            .iter()
+            .find(|language| language.matches(&name))
+            .or_else(|| {
+                let (_, extension) = name.rsplit_once('.')?;
+                LANGUAGES
+                    .iter()
+                    .find(|language| language.extensions.contains(&extension))
+            })
+    }
+
+    /// Every supported language
+    pub fn languages() -> &'static [Language] {
+        LANGUAGES
+    }
 }
 
 impl CodeBlock {
@@ -496,6 +716,8 @@ impl CodeBlock {
     }
 
     /// Apply syntax highlighting to the code content using lexing
+    ///
+    /// The content is the raw source text; every token is escaped exactly once.
     pub fn highlight(&self) -> String {
         debug!(
             "Starting highlight for language: '{}', content length: {}",
@@ -503,21 +725,12 @@ impl CodeBlock {
             self.content.len()
         );
 
-        // Decode HTML entities first, but be careful not to double-decode
-        let decoded_content = self
-            .content
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .replace("&amp;", "&")
-            .replace("&nbsp;", " ");
-        // Note: We don't decode &quot; and &#39; here as they might be intentional in code examples
-
         // Create language-specific lexer
         let lexer = LexerFactory::create_lexer(&self.language);
-        let tokens = lexer.lex(&decoded_content);
+        let tokens = lexer.lex(&self.content);
 
         // Convert tokens to HTML with proper escaping
-        let mut result = String::new();
+        let mut result = String::with_capacity(self.content.len() * 2);
         for token in tokens {
             let html_class = match token.token_type {
                 TokenType::Keyword => "keyword",
@@ -536,18 +749,22 @@ impl CodeBlock {
                 TokenType::Command => "command",
                 TokenType::Shebang => "shebang",
                 TokenType::Function => "function",
+                TokenType::Property => "property",
+                TokenType::Prompt => "prompt",
+                TokenType::Output => "output",
+                TokenType::Inserted => "inserted",
+                TokenType::Deleted => "deleted",
+                TokenType::Hunk => "hunk",
+                TokenType::FileHeader => "file-header",
             };
 
             if html_class.is_empty() {
-                // Escape HTML entities in the output, but preserve existing entities
-                let escaped_value = escape_html_entities(&token.value);
-                result.push_str(&escaped_value);
+                result.push_str(&escape_text(&token.value));
             } else {
-                // Escape HTML entities in the output, but preserve existing entities
-                let escaped_value = escape_html_entities(&token.value);
                 result.push_str(&format!(
                     "<span class=\"{}\">{}</span>",
-                    html_class, escaped_value
+                    html_class,
+                    escape_text(&token.value)
                 ));
             }
         }
@@ -557,324 +774,299 @@ impl CodeBlock {
     }
 }
 
-/// Escape HTML entities while preserving existing ones
-fn escape_html_entities(input: &str) -> String {
-    let mut result = String::new();
-    let mut i = 0;
-    let chars: Vec<char> = input.chars().collect();
-
-    while i < chars.len() {
-        let ch = chars[i];
-        match ch {
-            '&' => {
-                // Check if this is already an HTML entity
-                if i + 1 < chars.len() && chars[i + 1] == '#' {
-                    // Already an HTML entity, preserve it
-                    result.push(ch);
-                } else if i + 3 < chars.len()
-                    && chars[i + 1] == 'l'
-                    && chars[i + 2] == 't'
-                    && chars[i + 3] == ';'
-                {
-                    // Already &lt;
-                    result.push(ch);
-                } else if i + 3 < chars.len()
-                    && chars[i + 1] == 'g'
-                    && chars[i + 2] == 't'
-                    && chars[i + 3] == ';'
-                {
-                    // Already &gt;
-                    result.push(ch);
-                } else if i + 4 < chars.len()
-                    && chars[i + 1] == 'a'
-                    && chars[i + 2] == 'm'
-                    && chars[i + 3] == 'p'
-                    && chars[i + 4] == ';'
-                {
-                    // Already &amp;
-                    result.push(ch);
-                } else if i + 5 < chars.len()
-                    && chars[i + 1] == 'q'
-                    && chars[i + 2] == 'u'
-                    && chars[i + 3] == 'o'
-                    && chars[i + 4] == 't'
-                    && chars[i + 5] == ';'
-                {
-                    // Already &quot;
-                    result.push(ch);
-                } else if i + 4 < chars.len()
-                    && chars[i + 1] == '#'
-                    && chars[i + 2] == '3'
-                    && chars[i + 3] == '9'
-                    && chars[i + 4] == ';'
-                {
-                    // Already &#39;
-                    result.push(ch);
-                } else {
-                    // Regular &, escape it
-                    result.push_str("&amp;");
-                }
-            }
-            '<' => result.push_str("&lt;"),
-            '>' => result.push_str("&gt;"),
-            _ => result.push(ch),
-        }
-        i += 1;
+/// Token type of an unquoted value in a data format: literal, number or plain string
+fn scalar_type(value: &str, literals: &[&str]) -> TokenType {
+    let digits = value.trim_start_matches(['+', '-']);
+    let is_number = digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && (value.replace('_', "").parse::<f64>().is_ok()
+            || ["0x", "0o", "0b"].iter().any(|prefix| {
+                digits.starts_with(prefix) && digits.len() > 2
+                    && digits[2..].chars().all(|c| c.is_ascii_hexdigit() || c == '_')
+            })
+            || matches!(digits, ".inf" | ".Inf" | ".INF" | ".nan" | ".NaN" | ".NAN"));
+    if literals.contains(&value) {
+        TokenType::Keyword
+    } else if is_number {
+        TokenType::Number
+    } else {
+        TokenType::String
     }
-
-    result
 }
 
+/// Render a complete code block: container, header bar, copy button and code
+fn render_code_block(fence: &FenceInfo, code: &str) -> String {
+    debug!(
+        "Rendering code block - Language: '{}', Content length: {}",
+        fence.language,
+        code.len()
+    );
 
-/// Simple HTML parser for code block extraction without regex
-pub struct HTMLParser {
-    content: Vec<char>,
-    position: usize,
-}
-
-impl HTMLParser {
-    pub fn new(content: &str) -> Self {
-        Self {
-            content: content.chars().collect(),
-            position: 0,
-        }
+    // Without a language, go by the extension of the title, e.g. `title="Cargo.toml"`
+    let language = match (fence.language.as_str(), &fence.title) {
+        ("", Some(title)) => LexerFactory::find(title).map_or("text", |language| language.name),
+        ("", None) => "text",
+        (language, _) => language,
+    };
+    let display_language = if language == "text" { "TEXT" } else { language };
+
+    let highlighted = CodeBlock::new(language.to_string(), code.to_string()).highlight();
+
+    let mut classes = vec!["code-block-container"];
+    let mut header = String::new();
+    if fence.has_header() {
+        classes.push("has-header");
+        header = render_header(display_language, fence);
     }
+    // Diffs color whole lines, which needs the per-line wrappers. `diff-<language>` is a
+    // diff even when `<language>` is unknown, as in `create_lexer`
+    let is_diff = language.to_lowercase().starts_with("diff-")
+        || LexerFactory::find(language).is_some_and(|language| language.name == "diff");
+    let line_classes = if is_diff { diff::line_classes(code) } else { Vec::new() };
+    let highlighted = if fence.has_line_options() || !line_classes.is_empty() {
+        classes.push("has-lines");
+        if fence.line_numbers {
+            classes.push("has-line-numbers");
+        }
+        fence.render_lines(&highlighted, &line_classes)
+    } else {
+        highlighted
+    };
+
+    // The copy button only works with JavaScript, which unhides it
+    format!(
+        r#"<pre data-language="{}" class="{}">{}<button type="button" class="code-copy-btn" aria-label="Copy code to clipboard" hidden>Copy</button><code class="language-{}">{}</code></pre>
+"#,
+        escape_attr(display_language),
+        classes.join(" "),
+        header,
+        escape_attr(language),
+        highlighted
+    )
+}
 
-    pub fn current_char(&self) -> Option<char> {
-        self.content.get(self.position).copied()
+/// Header bar with the language badge, title and caption of a code block
+fn render_header(display_language: &str, fence: &FenceInfo) -> String {
+    let mut header = format!(
+        r#"<span class="code-block-header"><span class="code-block-language">{}</span>"#,
+        escape_text(display_language)
+    );
+    if let Some(title) = &fence.title {
+        header.push_str(&format!(
+            r#"<span class="code-block-title">{}</span>"#,
+            escape_text(title)
+        ));
     }
-
-    pub fn peek_char(&self, offset: usize) -> Option<char> {
-        self.content.get(self.position + offset).copied()
+    if let Some(caption) = &fence.caption {
+        header.push_str(&format!(
+            r#"<span class="code-block-caption">{}</span>"#,
+            escape_text(caption)
+        ));
     }
+    header.push_str("</span>");
+    header
+}
 
-    pub fn advance(&mut self) {
-        self.position += 1;
-    }
+/// Languages named in the fenced code blocks of a markdown document, in order
+pub fn fence_languages(content: &str) -> Vec<String> {
+    pulldown_cmark::Parser::new_ext(content, pulldown_cmark::Options::all())
+        .filter_map(|event| match event {
+            pulldown_cmark::Event::Start(pulldown_cmark::Tag::CodeBlock(
+                pulldown_cmark::CodeBlockKind::Fenced(info),
+            )) => Some(FenceInfo::parse(&info).language),
+            _ => None,
+        })
+        .filter(|language| !language.is_empty())
+        .collect()
+}
 
-    pub fn extract_code_blocks(&mut self) -> Vec<(String, String)> {
-        let mut code_blocks = Vec::new();
+/// Process markdown content, highlighting code blocks as they are parsed
+///
+/// Raw HTML in the markdown is sanitized with `allowlist`; `None` passes it through
+/// untouched, for trusted posts.
+pub fn process_markdown_content(content: &str, allowlist: Option<&Allowlist>) -> String {
+    debug!(
+        "Starting markdown processing, content length: {}",
+        content.len()
+    );
 
-        while self.position < self.content.len() {
-            // Look for <pre><code
-            if self.current_char() == Some('<')
-                && self.peek_char(1) == Some('p')
-                && self.peek_char(2) == Some('r')
-                && self.peek_char(3) == Some('e')
-                && self.peek_char(4) == Some('>')
-            {
-                // Skip <pre>
-                for _ in 0..5 {
-                    self.advance();
+    // Process the markdown content with pulldown-cmark, rendering math to MathML and
+    // code blocks to highlighted HTML. Both are added after sanitizing, so they never
+    // go through the allowlist.
+    let mut events = Vec::new();
+    // Lines of the current raw HTML block, sanitized together so tags spanning lines survive
+    let mut html_block = String::new();
+    // Inline HTML and the text between its tags, sanitized together so that the body of
+    // an inline `<script>` goes with it
+    let mut inline_html = String::new();
+    // Options and source text of the code block being read
+    let mut code_block: Option<(FenceInfo, String)> = None;
+    // Keeps the allowed raw HTML balanced within each markdown container
+    let mut sanitizer = allowlist.map(Sanitizer::new);
+    for event in pulldown_cmark::Parser::new_ext(content, pulldown_cmark::Options::all()) {
+        let event = match (event, allowlist) {
+            (pulldown_cmark::Event::Start(pulldown_cmark::Tag::CodeBlock(kind)), _) => {
+                let fence = match kind {
+                    pulldown_cmark::CodeBlockKind::Fenced(info) => FenceInfo::parse(&info),
+                    pulldown_cmark::CodeBlockKind::Indented => FenceInfo::default(),
+                };
+                code_block = Some((fence, String::new()));
+                continue;
+            }
+            (pulldown_cmark::Event::Text(text), _) if code_block.is_some() => {
+                if let Some((_, code)) = code_block.as_mut() {
+                    code.push_str(&text);
                 }
-
-                // Look for <code
-                if self.current_char() == Some('<')
-                    && self.peek_char(1) == Some('c')
-                    && self.peek_char(2) == Some('o')
-                    && self.peek_char(3) == Some('d')
-                    && self.peek_char(4) == Some('e')
-                {
-                    // Skip <code
-                    for _ in 0..5 {
-                        self.advance();
-                    }
-
-                    // Extract language from class attribute
-                    let mut language = "text".to_string();
-                    if self.current_char() == Some(' ')
-                        && self.peek_char(1) == Some('c')
-                        && self.peek_char(2) == Some('l')
-                        && self.peek_char(3) == Some('a')
-                        && self.peek_char(4) == Some('s')
-                        && self.peek_char(5) == Some('s')
-                        && self.peek_char(6) == Some('=')
-                        && self.peek_char(7) == Some('"')
-                    {
-                        // Skip class="
-                        for _ in 0..8 {
-                            self.advance();
-                        }
-
-                        // Extract language
-                        let mut lang = String::new();
-                        while let Some(c) = self.current_char() {
-                            if c == '"' {
-                                self.advance();
-                                break;
-                            }
-                            lang.push(c);
-                            self.advance();
-                        }
-
-                        if let Some(stripped) = lang.strip_prefix("language-") {
-                            language = stripped.to_string();
-                        }
-                    }
-
-                    // Skip to >
-                    while let Some(c) = self.current_char() {
-                        if c == '>' {
-                            self.advance();
-                            break;
-                        }
-                        self.advance();
-                    }
-
-                    // Extract code content until </code></pre>
-                    let mut code_content = String::new();
-
-                    while self.position < self.content.len() {
-                        if self.current_char() == Some('<')
-                            && self.peek_char(1) == Some('/')
-                            && self.peek_char(2) == Some('c')
-                            && self.peek_char(3) == Some('o')
-                            && self.peek_char(4) == Some('d')
-                            && self.peek_char(5) == Some('e')
-                            && self.peek_char(6) == Some('>')
-                        {
-                            // Found </code>
-                            for _ in 0..7 {
-                                self.advance();
-                            }
-
-                            // Look for </pre>
-                            if self.current_char() == Some('<')
-                                && self.peek_char(1) == Some('/')
-                                && self.peek_char(2) == Some('p')
-                                && self.peek_char(3) == Some('r')
-                                && self.peek_char(4) == Some('e')
-                                && self.peek_char(5) == Some('>')
-                            {
-                                // Found </pre>
-                                for _ in 0..6 {
-                                    self.advance();
-                                }
-                                break;
-                            }
-                        } else {
-                            if let Some(c) = self.current_char() {
-                                code_content.push(c);
-                            }
-                            self.advance();
-                        }
+                continue;
+            }
+            (pulldown_cmark::Event::End(pulldown_cmark::TagEnd::CodeBlock), _) => {
+                let Some((fence, code)) = code_block.take() else {
+                    continue;
+                };
+                pulldown_cmark::Event::Html(render_code_block(&fence, &code).into())
+            }
+            (pulldown_cmark::Event::Html(html), Some(_)) => {
+                html_block.push_str(&html);
+                continue;
+            }
+            (pulldown_cmark::Event::InlineHtml(html), Some(_)) => {
+                inline_html.push_str(&html);
+                continue;
+            }
+            (pulldown_cmark::Event::Text(text), Some(_)) if !inline_html.is_empty() => {
+                inline_html.push_str(&escape_text(&text));
+                continue;
+            }
+            (pulldown_cmark::Event::SoftBreak, Some(_)) if !inline_html.is_empty() => {
+                inline_html.push('\n');
+                continue;
+            }
+            // Markdown links and images are held to the same URL rules as raw HTML
+            (
+                pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }),
+                Some(_),
+            ) => pulldown_cmark::Event::Start(pulldown_cmark::Tag::Link {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            (
+                pulldown_cmark::Event::Start(pulldown_cmark::Tag::Image {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }),
+                Some(_),
+            ) => pulldown_cmark::Event::Start(pulldown_cmark::Tag::Image {
+                link_type,
+                dest_url: safe_url(dest_url),
+                title,
+                id,
+            }),
+            (pulldown_cmark::Event::InlineMath(tex), _) => {
+                pulldown_cmark::Event::InlineHtml(render_math(&tex, false).into())
+            }
+            (pulldown_cmark::Event::DisplayMath(tex), _) => {
+                pulldown_cmark::Event::InlineHtml(render_math(&tex, true).into())
+            }
+            (other, _) => other,
+        };
+        if let Some(sanitizer) = sanitizer.as_mut() {
+            flush_sanitized(&mut events, &mut html_block, &mut inline_html, sanitizer);
+            match &event {
+                // HTML blocks end at blank lines, so elements may span several of them
+                pulldown_cmark::Event::Start(pulldown_cmark::Tag::HtmlBlock)
+                | pulldown_cmark::Event::End(pulldown_cmark::TagEnd::HtmlBlock) => {}
+                pulldown_cmark::Event::Start(_) => sanitizer.enter(),
+                pulldown_cmark::Event::End(_) => {
+                    let closing = sanitizer.leave();
+                    if !closing.is_empty() {
+                        events.push(pulldown_cmark::Event::InlineHtml(closing.into()));
                     }
-
-                    code_blocks.push((language, code_content));
                 }
-            } else {
-                self.advance();
+                _ => {}
             }
         }
-
-        code_blocks
+        events.push(event);
+    }
+    if let Some(sanitizer) = sanitizer.as_mut() {
+        flush_sanitized(&mut events, &mut html_block, &mut inline_html, sanitizer);
+        let closing = sanitizer.finish();
+        if !closing.is_empty() {
+            events.push(pulldown_cmark::Event::Html(closing.into()));
+        }
     }
-}
-
-/// Process markdown content and enhance code blocks
-pub fn process_markdown_content(content: &str) -> String {
-    debug!(
-        "Starting markdown processing, content length: {}",
-        content.len()
-    );
 
-    // First, process the markdown content with pulldown-cmark
-    let parser = pulldown_cmark::Parser::new_ext(content, pulldown_cmark::Options::all());
-    let mut html_output = String::new();
-    pulldown_cmark::html::push_html(&mut html_output, parser);
+    let mut html_output = String::with_capacity(content.len() * 2);
+    pulldown_cmark::html::push_html(&mut html_output, events.into_iter());
 
     debug!("Markdown processed, HTML length: {}", html_output.len());
-
-    // Now enhance the HTML code blocks with syntax highlighting
-    let enhanced_html = enhance_html_code_blocks(&html_output);
-
-    debug!(
-        "HTML code blocks enhanced, final length: {}",
-        enhanced_html.len()
-    );
-    enhanced_html
+    html_output
 }
 
-/// Enhance HTML code blocks with syntax highlighting
-fn enhance_html_code_blocks(html: &str) -> String {
-    debug!("Enhancing HTML code blocks, input length: {}", html.len());
-
-    let mut parser = HTMLParser::new(html);
-    let code_blocks = parser.extract_code_blocks();
-
-    debug!("Extracted {} HTML code blocks", code_blocks.len());
-
-    // Replace code blocks with enhanced versions
-    let mut result = html.to_string();
-    let mut offset = 0;
-
-    for (language, code_content) in code_blocks {
-        debug!(
-            "Found HTML code block - Language: '{}', Content length: {}",
-            language,
-            code_content.len()
-        );
-        debug!(
-            "Code block content preview: {}",
-            &code_content[..code_content.len().min(100)]
-        );
-
-        // Try different search patterns for different language scenarios
-        let search_patterns = vec![
-            format!(
-                "<pre><code class=\"language-{}\">{}</code></pre>",
-                language, code_content
-            ),
-            format!("<pre><code>{}</code></pre>", code_content), // For plain text without class
-            format!(
-                "<pre><code class=\"{}\">{}</code></pre>",
-                language, code_content
-            ), // Without language- prefix
-        ];
-
-        let mut found_pattern = None;
-        let mut actual_start = 0;
-        let mut end_pos = 0;
-
-        for pattern in &search_patterns {
-            if let Some(start_pos) = result[offset..].find(pattern) {
-                actual_start = offset + start_pos;
-                end_pos = actual_start + pattern.len();
-                found_pattern = Some(pattern);
-                debug!("Found pattern: {}", pattern);
-                break;
-            }
-        }
-
-        if let Some(_pattern) = found_pattern {
-            // Use fallback text for unspecified languages
-            debug!("Language: {}", language);
-            let display_language = if language.is_empty() || language == "text" {
-                "TEXT"
-            } else {
-                &language
-            };
+/// Sanitize and emit buffered raw HTML
+fn flush_sanitized<'a>(
+    events: &mut Vec<pulldown_cmark::Event<'a>>,
+    html_block: &mut String,
+    inline_html: &mut String,
+    sanitizer: &mut Sanitizer,
+) {
+    if !html_block.is_empty() {
+        events.push(pulldown_cmark::Event::Html(sanitizer.sanitize(html_block).into()));
+        html_block.clear();
+    }
+    if !inline_html.is_empty() {
+        events.push(pulldown_cmark::Event::InlineHtml(sanitizer.sanitize(inline_html).into()));
+        inline_html.clear();
+    }
+}
 
-            // For text language, don't apply syntax highlighting
-            let final_content = if language == "text" {
-                // Just escape HTML entities for plain text
-                escape_html_entities(&code_content)
-            } else {
-                // Apply syntax highlighting for other languages
-                let code_block = CodeBlock::new(language.clone(), code_content.clone());
-                code_block.highlight()
-            };
+/// A link or image URL, blanked if it uses a scheme other than http, https or mailto
+fn safe_url(url: pulldown_cmark::CowStr<'_>) -> pulldown_cmark::CowStr<'_> {
+    if is_safe_url(&url) {
+        url
+    } else {
+        debug!("Dropping unsafe URL in post: {url:?}");
+        pulldown_cmark::CowStr::Borrowed("")
+    }
+}
 
-            let enhanced_html = format!(
-                r#"<pre data-language="{}" class="code-block-container"><code class="language-{}">{}</code></pre>"#,
-                display_language, language, final_content
-            );
+/// Tokens of `code` lexed as `language`, without whitespace and newlines
+#[cfg(test)]
+fn significant_tokens(language: &str, code: &str) -> Vec<(TokenType, String)> {
+    LexerFactory::create_lexer(language)
+        .lex(code)
+        .into_iter()
+        .filter(|t| !matches!(t.token_type, TokenType::Whitespace | TokenType::Newline))
+        .map(|t| (t.token_type, t.value))
+        .collect()
+}
 
-            // Replace the original with enhanced version
-            result.replace_range(actual_start..end_pos, &enhanced_html);
-            offset = actual_start + enhanced_html.len();
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_registry() {
+        assert_eq!(LexerFactory::find("RS").map(|l| l.name), Some("rust"));
+        assert_eq!(LexerFactory::find("c++").map(|l| l.name), Some("cpp"));
+        assert_eq!(LexerFactory::find("src/main.rs").map(|l| l.name), Some("rust"));
+        assert!(LexerFactory::find("go").is_none());
+
+        // Unknown languages are plain text, not another language's keywords
+        let tokens = LexerFactory::create_lexer("go").lex("func main() {}");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].token_type, TokenType::Other);
+
+        let html = process_markdown_content("```text title=\"main.rs\"\nfn\n```\n", None);
+        assert!(html.contains("<code class=\"language-text\">fn"), "{html}");
+        let html = process_markdown_content("``` title=\"main.rs\"\nfn\n```\n", None);
+        assert!(html.contains("<span class=\"keyword\">fn</span>"), "{html}");
     }
-
-    result
 }
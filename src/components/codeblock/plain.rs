@@ -0,0 +1,21 @@
+use super::LanguageLexer;
+use super::{TokenType, Token};
+
+/// Plain text, also the fallback for unknown languages: the whole input as one
+/// unstyled token
+pub struct PlainTextLexer;
+
+impl LanguageLexer for PlainTextLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        vec![Token {
+            token_type: TokenType::Other,
+            value: input.to_string(),
+            start: 0,
+            end: input.chars().count(),
+        }]
+    }
+}
@@ -0,0 +1,196 @@
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+
+/// What differs between the SSA-form IRs that share [`lex_ssa`]
+pub struct SsaDialect {
+    /// Instructions and other reserved words
+    pub keywords: &'static [&'static str],
+    /// Type names besides `iN` integers
+    pub types: &'static [&'static str],
+    /// Linkage, parameter and function attributes
+    pub attributes: &'static [&'static str],
+    pub line_comment: &'static str,
+    /// Whether `!name` is metadata and `#0` an attribute group, as in LLVM
+    pub metadata: bool,
+}
+
+impl SsaDialect {
+    fn is_type(&self, word: &str) -> bool {
+        self.types.contains(&word)
+            || word
+                .strip_prefix('i')
+                .is_some_and(|bits| !bits.is_empty() && bits.chars().all(|c| c.is_ascii_digit()))
+    }
+}
+
+/// Lex an SSA-form IR, so that values, types and basic-block labels look the same in
+/// every IR
+///
+/// `%locals` are variables, `@globals` identifiers, types types, and a name followed by
+/// `:` at the start of a line a block label. `add.i32` style suffixes are split off and
+/// lexed as types.
+pub fn lex_ssa(input: &str, dialect: &SsaDialect) -> Vec<Token> {
+    let mut lexer = BaseLexer::new(input);
+    // Only indentation seen so far on this line, so a name with `:` is a label
+    let mut line_start = true;
+
+    while lexer.position < lexer.input.len() {
+        let ch = lexer.current_char().unwrap_or('\0');
+        if dialect
+            .line_comment
+            .chars()
+            .enumerate()
+            .all(|(i, c)| lexer.peek_char(i) == Some(c))
+        {
+            lexer.consume_single_line_comment(dialect.line_comment);
+            continue;
+        }
+
+        match ch {
+            ' ' | '\t' => {
+                lexer.consume_whitespace();
+                continue;
+            }
+            '\n' | '\r' => {
+                lexer.consume_newlines();
+                line_start = true;
+                continue;
+            }
+            '"' => {
+                lexer.consume_string('"');
+            }
+            'c' if lexer.peek_char(1) == Some('"') => {
+                // LLVM byte string, c"hello\00"
+                let start = lexer.position;
+                lexer.advance();
+                lexer.consume_string('"');
+                if let Some(token) = lexer.tokens.last_mut() {
+                    token.value.insert(0, 'c');
+                    token.start = start;
+                }
+            }
+            '%' | '@' => {
+                let start = lexer.position;
+                lexer.advance();
+                let name = if lexer.current_char() == Some('"') {
+                    consume_quoted_name(&mut lexer)
+                } else {
+                    lexer.take_while(is_name_char)
+                };
+                let token_type = if ch == '%' {
+                    TokenType::Variable
+                } else {
+                    TokenType::Identifier
+                };
+                lexer.add_token(token_type, format!("{ch}{name}"), start, lexer.position);
+            }
+            '!' | '#'
+                if dialect.metadata
+                    && lexer
+                        .peek_char(1)
+                        .is_some_and(|c| is_name_char(c) || (ch == '!' && c == '"')) =>
+            {
+                // Metadata like !0, !dbg and !"string", attribute groups like #0
+                let start = lexer.position;
+                lexer.advance();
+                let name = if lexer.current_char() == Some('"') {
+                    consume_quoted_name(&mut lexer)
+                } else {
+                    lexer.take_while(is_name_char)
+                };
+                lexer.add_token(
+                    TokenType::Macro,
+                    format!("{ch}{name}"),
+                    start,
+                    lexer.position,
+                );
+            }
+            '0'..='9' => {
+                if line_start && label_follows(&lexer) {
+                    let start = lexer.position;
+                    let value = lexer.take_while(is_name_char);
+                    lexer.add_token(TokenType::Function, value, start, lexer.position);
+                } else {
+                    lexer.consume_number();
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = lexer.position;
+                if line_start && label_follows(&lexer) {
+                    let value = lexer.take_while(is_name_char);
+                    lexer.add_token(TokenType::Function, value, start, lexer.position);
+                } else {
+                    lex_word(&mut lexer, dialect);
+                }
+            }
+            '<' | '>' | '=' | '+' | '-' | '*' | '&' | '|' | '!' | '?' | ':' | ';' | ',' | '.'
+            | '(' | ')' | '[' | ']' | '{' | '}' | '#' => {
+                lexer.add_operator(ch.to_string());
+            }
+            _ => {
+                lexer.add_other(ch.to_string());
+            }
+        }
+        line_start = false;
+    }
+
+    lexer.get_tokens()
+}
+
+/// A bare word, with `.` separated parts such as the `i32` of `add.i32` lexed on their own
+fn lex_word(lexer: &mut BaseLexer, dialect: &SsaDialect) {
+    let mut first = true;
+    loop {
+        let start = lexer.position;
+        let word = lexer.take_while(|c| c.is_alphanumeric() || c == '_');
+        let token_type = if dialect.is_type(&word) {
+            TokenType::Type
+        } else if first && dialect.keywords.contains(&word.as_str()) {
+            TokenType::Keyword
+        } else if dialect.attributes.contains(&word.as_str()) {
+            TokenType::Macro
+        } else {
+            TokenType::Identifier
+        };
+        lexer.add_token(token_type, word, start, lexer.position);
+
+        if lexer.current_char() == Some('.')
+            && lexer
+                .peek_char(1)
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            lexer.add_operator(".".to_string());
+            first = false;
+        } else {
+            return;
+        }
+    }
+}
+
+/// Whether the name at the current position is directly followed by `:`
+fn label_follows(lexer: &BaseLexer) -> bool {
+    let mut offset = 0;
+    while lexer.peek_char(offset).is_some_and(is_name_char) {
+        offset += 1;
+    }
+    lexer.peek_char(offset) == Some(':')
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '$' | '-')
+}
+
+/// `"name with spaces"` after a `%`, `@` or `!` sigil
+fn consume_quoted_name(lexer: &mut BaseLexer) -> String {
+    let mut value = String::from('"');
+    lexer.advance();
+    while let Some(c) = lexer.current_char() {
+        value.push(c);
+        lexer.advance();
+        if c == '"' {
+            break;
+        }
+    }
+    value
+}
@@ -0,0 +1,186 @@
+use super::LanguageLexer;
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+
+/// TOML: table headers, keys, all four string forms, numbers, dates and booleans
+pub struct TomlLexer;
+
+impl LanguageLexer for TomlLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &["true", "false"]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let keywords = self.get_keywords();
+        let mut lexer = BaseLexer::new(input);
+        // Only indentation seen so far on this line, so `[` starts a table header
+        let mut line_start = true;
+
+        while lexer.position < lexer.input.len() {
+            let ch = lexer.current_char().unwrap_or('\0');
+            match ch {
+                ' ' | '\t' => {
+                    lexer.consume_whitespace();
+                    continue;
+                }
+                '\n' | '\r' => {
+                    lexer.consume_newlines();
+                    line_start = true;
+                    continue;
+                }
+                '#' => {
+                    lexer.consume_single_line_comment("#");
+                }
+                '[' if line_start => {
+                    // `[table]` or `[[array.of.tables]]`
+                    let start = lexer.position;
+                    let mut value = lexer.take_while(|c| c == '[');
+                    value.push_str(&lexer.take_while(|c| c != ']' && c != '\n' && c != '\r'));
+                    value.push_str(&lexer.take_while(|c| c == ']'));
+                    lexer.add_token(TokenType::Type, value, start, lexer.position);
+                }
+                '"' | '\'' => {
+                    let start = lexer.position;
+                    let value = if lexer.peek_char(1) == Some(ch) && lexer.peek_char(2) == Some(ch)
+                    {
+                        consume_multi_line_string(&mut lexer, ch)
+                    } else {
+                        consume_single_line_string(&mut lexer, ch)
+                    };
+                    let token_type = if lexer.next_on_line_is(&['=', '.']) {
+                        TokenType::Property
+                    } else {
+                        TokenType::String
+                    };
+                    lexer.add_token(token_type, value, start, lexer.position);
+                }
+                '0'..='9' | '+' | '-'
+                    if ch.is_ascii_digit()
+                        || lexer
+                            .peek_char(1)
+                            .is_some_and(|c| c.is_ascii_digit() || c == 'i' || c == 'n') =>
+                {
+                    // Numbers, `inf`/`nan` and dates such as 1979-05-27T07:32:00-08:00
+                    let start = lexer.position;
+                    let mut value = ch.to_string();
+                    lexer.advance();
+                    value.push_str(&lexer.take_while(|c| {
+                        c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | ':' | '-' | '+')
+                    }));
+                    let token_type = if lexer.next_on_line_is(&['=']) {
+                        TokenType::Property
+                    } else {
+                        TokenType::Number
+                    };
+                    lexer.add_token(token_type, value, start, lexer.position);
+                }
+                'a'..='z' | 'A'..='Z' | '_' | '-' => {
+                    let start = lexer.position;
+                    let value = lexer.take_while(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                    let token_type = if lexer.next_on_line_is(&['=', '.']) {
+                        // Bare key, or one part of a dotted key
+                        TokenType::Property
+                    } else if keywords.contains(&value.as_str()) {
+                        TokenType::Keyword
+                    } else if value == "inf" || value == "nan" {
+                        TokenType::Number
+                    } else {
+                        TokenType::Identifier
+                    };
+                    lexer.add_token(token_type, value, start, lexer.position);
+                }
+                '=' | '[' | ']' | '{' | '}' | ',' | '.' => {
+                    lexer.add_operator(ch.to_string());
+                }
+                _ => {
+                    lexer.add_other(ch.to_string());
+                }
+            }
+            line_start = false;
+        }
+        lexer.get_tokens()
+    }
+}
+
+/// `"basic"` with escapes or `'literal'` without, ending at the line
+fn consume_single_line_string(lexer: &mut BaseLexer, quote: char) -> String {
+    let mut value = String::from(quote);
+    lexer.advance();
+    while let Some(c) = lexer.current_char() {
+        if c == '\n' || c == '\r' {
+            break;
+        }
+        value.push(c);
+        lexer.advance();
+        if c == '\\' && quote == '"' {
+            if let Some(escaped) = lexer.current_char() {
+                value.push(escaped);
+                lexer.advance();
+            }
+        } else if c == quote {
+            break;
+        }
+    }
+    value
+}
+
+/// `"""basic"""` or `'''literal'''`, which may span lines
+fn consume_multi_line_string(lexer: &mut BaseLexer, quote: char) -> String {
+    let delimiter: String = [quote; 3].iter().collect();
+    let mut value = delimiter.clone();
+    for _ in 0..3 {
+        lexer.advance();
+    }
+    while let Some(c) = lexer.current_char() {
+        value.push(c);
+        lexer.advance();
+        if c == '\\' && quote == '"' {
+            if let Some(escaped) = lexer.current_char() {
+                value.push(escaped);
+                lexer.advance();
+            }
+        } else if value.len() >= 6 && value.ends_with(&delimiter) {
+            // Up to two more quotes may belong to the content
+            value.push_str(&lexer.take_while(|c| c == quote));
+            break;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_toml_lexer() {
+        let tokens = significant_tokens(
+            "toml",
+            "[server]\nhost.name = \"x\" # c\nports = [80, 443]\n",
+        );
+        let tokens: Vec<_> = tokens
+            .iter()
+            .map(|(t, v)| (t.clone(), v.as_str()))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Type, "[server]"),
+                (TokenType::Property, "host"),
+                (TokenType::Operator, "."),
+                (TokenType::Property, "name"),
+                (TokenType::Operator, "="),
+                (TokenType::String, "\"x\""),
+                (TokenType::Comment, "# c"),
+                (TokenType::Property, "ports"),
+                (TokenType::Operator, "="),
+                (TokenType::Operator, "["),
+                (TokenType::Number, "80"),
+                (TokenType::Operator, ","),
+                (TokenType::Number, "443"),
+                (TokenType::Operator, "]"),
+            ]
+        );
+    }
+}
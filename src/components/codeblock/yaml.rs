@@ -0,0 +1,265 @@
+use super::LanguageLexer;
+use super::TokenType;
+use super::Token;
+use super::BaseLexer;
+use super::scalar_type;
+
+/// YAML: keys, list markers, block and flow collections, scalars, anchors and tags
+pub struct YamlLexer;
+
+impl LanguageLexer for YamlLexer {
+    fn get_keywords(&self) -> &[&str] {
+        &[
+            "true", "false", "True", "False", "TRUE", "FALSE", "yes", "no", "on", "off", "null",
+            "Null", "NULL", "~",
+        ]
+    }
+
+    fn lex(&self, input: &str) -> Vec<Token> {
+        let literals = self.get_keywords();
+        let mut lexer = BaseLexer::new(input);
+        // Indentation of the line that opened a `|` or `>` block scalar
+        let mut block_scalar: Option<usize> = None;
+
+        while lexer.position < lexer.input.len() {
+            let line_start = lexer.position;
+            lexer.consume_whitespace();
+            let indent = lexer.position - line_start;
+            let blank = matches!(lexer.current_char(), None | Some('\n' | '\r'));
+
+            // Lines of a block scalar are text, whatever they look like
+            if let Some(parent) = block_scalar {
+                if blank || indent > parent {
+                    let start = lexer.position;
+                    let value = lexer.take_while(|c| c != '\n' && c != '\r');
+                    if !value.is_empty() {
+                        lexer.add_token(TokenType::String, value, start, lexer.position);
+                    }
+                    lexer.consume_newlines();
+                    continue;
+                }
+                block_scalar = None;
+            }
+
+            if lex_line(&mut lexer, literals, indent == 0) {
+                block_scalar = Some(indent);
+            }
+            lexer.consume_newlines();
+        }
+        lexer.get_tokens()
+    }
+}
+
+/// Lex one line after its indentation, returning whether it opens a block scalar
+fn lex_line(lexer: &mut BaseLexer, literals: &[&str], column_zero: bool) -> bool {
+    // `---` and `...` document markers
+    if column_zero {
+        for marker in ["---", "..."] {
+            let matches = marker
+                .chars()
+                .enumerate()
+                .all(|(i, c)| lexer.peek_char(i) == Some(c));
+            if matches && lexer.peek_char(3).is_none_or(char::is_whitespace) {
+                let start = lexer.position;
+                for _ in 0..3 {
+                    lexer.advance();
+                }
+                lexer.add_token(TokenType::Keyword, marker.to_string(), start, lexer.position);
+                return lex_value(lexer, literals);
+            }
+        }
+    }
+
+    // `- ` sequence entries and `? ` complex keys, possibly several on one line
+    while matches!(lexer.current_char(), Some('-' | '?'))
+        && lexer.peek_char(1).is_none_or(char::is_whitespace)
+    {
+        let marker = lexer.current_char().unwrap_or('-');
+        lexer.add_operator(marker.to_string());
+        lexer.consume_whitespace();
+    }
+
+    if lexer.current_char() == Some('#') {
+        lexer.consume_single_line_comment("#");
+        return false;
+    }
+
+    if let Some(length) = key_length(&lexer.input[lexer.position..]) {
+        let start = lexer.position;
+        let key: String = lexer.input[start..start + length].iter().collect();
+        lexer.position += length;
+        lexer.add_token(TokenType::Property, key, start, lexer.position);
+        lexer.consume_whitespace();
+        lexer.add_operator(":".to_string());
+    }
+    lex_value(lexer, literals)
+}
+
+/// Length of the mapping key at the start of `line`, if the line has one
+///
+/// A key ends at a `:` followed by whitespace or the end of the line.
+fn key_length(line: &[char]) -> Option<usize> {
+    let first = *line.first()?;
+    if matches!(first, '[' | '{' | '|' | '>' | '#' | '&' | '*' | '!') {
+        return None;
+    }
+
+    let mut i = 0;
+    if first == '"' || first == '\'' {
+        i = 1 + line[1..].iter().position(|&c| c == first)? + 1;
+    }
+    while i < line.len() {
+        match line[i] {
+            '\n' | '\r' => return None,
+            '#' if i > 0 && line[i - 1].is_whitespace() => return None,
+            ':' if line.get(i + 1).is_none_or(|c| c.is_whitespace()) => {
+                let end = line[..i].iter().rposition(|c| !c.is_whitespace())? + 1;
+                return Some(end);
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Lex the value part of a line, returning whether it opens a block scalar
+fn lex_value(lexer: &mut BaseLexer, literals: &[&str]) -> bool {
+    loop {
+        match lexer.current_char() {
+            None | Some('\n' | '\r') => return false,
+            Some(' ' | '\t') => {
+                lexer.consume_whitespace();
+            }
+            Some('#') => {
+                lexer.consume_single_line_comment("#");
+                return false;
+            }
+            Some(c @ ('&' | '*' | '!')) => {
+                // Anchor, alias or tag
+                let start = lexer.position;
+                let value = lexer.take_while(|c| !c.is_whitespace());
+                let token_type = if c == '!' {
+                    TokenType::Type
+                } else {
+                    TokenType::Variable
+                };
+                lexer.add_token(token_type, value, start, lexer.position);
+            }
+            Some('|' | '>') => {
+                let start = lexer.position;
+                let value =
+                    lexer.take_while(|c| matches!(c, '|' | '>' | '-' | '+') || c.is_ascii_digit());
+                lexer.add_token(TokenType::Operator, value, start, lexer.position);
+                lexer.consume_whitespace();
+                if lexer.current_char() == Some('#') {
+                    lexer.consume_single_line_comment("#");
+                }
+                return true;
+            }
+            Some(quote @ ('"' | '\'')) => {
+                lexer.consume_string(quote);
+            }
+            Some('[' | '{' | ']' | '}' | ',') => lex_flow(lexer, literals),
+            Some(_) => {
+                let start = lexer.position;
+                let value = take_plain_scalar(lexer, &[]);
+                add_scalar(lexer, value, start, literals);
+            }
+        }
+    }
+}
+
+/// Lex a `[a, b]` or `{a: 1}` flow collection up to the end of the line
+fn lex_flow(lexer: &mut BaseLexer, literals: &[&str]) {
+    const INDICATORS: &[char] = &['[', ']', '{', '}', ',', ':'];
+    loop {
+        match lexer.current_char() {
+            None | Some('\n' | '\r') => return,
+            Some(' ' | '\t') => {
+                lexer.consume_whitespace();
+            }
+            Some('#') => {
+                lexer.consume_single_line_comment("#");
+                return;
+            }
+            Some(c) if INDICATORS.contains(&c) => lexer.add_operator(c.to_string()),
+            Some(quote @ ('"' | '\'')) => {
+                lexer.consume_string(quote);
+                if lexer.next_on_line_is(&[':']) {
+                    lexer.retype_last_token(TokenType::Property);
+                }
+            }
+            Some(_) => {
+                let start = lexer.position;
+                let value = take_plain_scalar(lexer, INDICATORS);
+                if lexer.next_on_line_is(&[':']) {
+                    let key = value.trim_end().to_string();
+                    let rest = value[key.len()..].to_string();
+                    lexer.add_token(TokenType::Property, key, start, lexer.position);
+                    if !rest.is_empty() {
+                        lexer.add_token(TokenType::Whitespace, rest, start, lexer.position);
+                    }
+                } else {
+                    add_scalar(lexer, value, start, literals);
+                }
+            }
+        }
+    }
+}
+
+/// Take an unquoted scalar up to the end of the line, a comment or one of `stop`
+fn take_plain_scalar(lexer: &mut BaseLexer, stop: &[char]) -> String {
+    let mut value = String::new();
+    while let Some(c) = lexer.current_char() {
+        let comment = c == '#' && value.ends_with(char::is_whitespace);
+        if c == '\n' || c == '\r' || comment || stop.contains(&c) {
+            break;
+        }
+        value.push(c);
+        lexer.advance();
+    }
+    value
+}
+
+/// Add a plain scalar, keeping trailing whitespace out of its token
+fn add_scalar(lexer: &mut BaseLexer, value: String, start: usize, literals: &[&str]) {
+    let trimmed = value.trim_end();
+    let token_type = scalar_type(trimmed, literals);
+    let rest = value[trimmed.len()..].to_string();
+    lexer.add_token(token_type, trimmed.to_string(), start, lexer.position);
+    if !rest.is_empty() {
+        lexer.add_token(TokenType::Whitespace, rest, start, lexer.position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TokenType, significant_tokens};
+
+    #[test]
+    fn test_yaml_lexer() {
+        let tokens = significant_tokens("yml", "- name: web # c\n  run: |\n    a: b\n  on: true\n");
+        let tokens: Vec<_> = tokens
+            .iter()
+            .map(|(t, v)| (t.clone(), v.as_str()))
+            .collect();
+        // The block scalar is one string, even though it looks like a mapping
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenType::Operator, "-"),
+                (TokenType::Property, "name"),
+                (TokenType::Operator, ":"),
+                (TokenType::String, "web"),
+                (TokenType::Comment, "# c"),
+                (TokenType::Property, "run"),
+                (TokenType::Operator, ":"),
+                (TokenType::Operator, "|"),
+                (TokenType::String, "a: b"),
+                (TokenType::Property, "on"),
+                (TokenType::Operator, ":"),
+                (TokenType::Keyword, "true"),
+            ]
+        );
+    }
+}
@@ -2,29 +2,67 @@ use std::collections::HashMap;
 
 use crate::components::ui::render_component;
 
-/// Preprocess markdown content to replace custom component shortcode syntax with HTML
+/// Private-use characters around a placeholder; markdown passes them through as plain text
+const PLACEHOLDER_START: char = '\u{E000}';
+const PLACEHOLDER_END: char = '\u{E001}';
+
+/// Markdown with shortcodes swapped for placeholders, plus the HTML each one stands for
+///
+/// Raw HTML in markdown is escaped, so rendered shortcodes are kept out of the markdown
+/// and put back with [`restore_components`] afterwards.
+pub struct Preprocessed {
+    pub markdown: String,
+    pub components: Vec<String>,
+}
+
+/// Preprocess markdown content to replace custom component shortcode syntax with placeholders
 ///
 /// Supported syntax examples (non-HTML, markdown-friendly):
 /// - [[image src="/static/path.jpg" alt="Alt text" caption="Caption text"]]
 /// - [[img src="..." alt="..."]]
 /// - [[component image src="..." alt="..." caption="..."]]
-pub fn preprocess_markdown_with_components(input: &str) -> String {
+pub fn preprocess_markdown_with_components(input: &str) -> Preprocessed {
     let mut output = String::with_capacity(input.len());
+    let mut components = Vec::new();
     let bytes = input.as_bytes();
     let mut i = 0;
 
     while i < bytes.len() {
-        if bytes[i] == b'[' && i + 1 < bytes.len() && bytes[i + 1] == b'[' {
-            if let Some((consumed, replacement)) = try_parse_bracket_component(&input[i..]) {
-                output.push_str(&replacement);
-                i += consumed;
-                continue;
-            }
+        if bytes[i] == b'['
+            && i + 1 < bytes.len()
+            && bytes[i + 1] == b'['
+            && let Some((consumed, replacement)) = try_parse_bracket_component(&input[i..])
+        {
+            output.push(PLACEHOLDER_START);
+            output.push_str(&components.len().to_string());
+            output.push(PLACEHOLDER_END);
+            components.push(replacement);
+            i += consumed;
+            continue;
         }
-        output.push(bytes[i] as char);
-        i += 1;
+        let ch = input[i..].chars().next().unwrap_or_default();
+        output.push(ch);
+        i += ch.len_utf8();
     }
 
+    Preprocessed {
+        markdown: output,
+        components,
+    }
+}
+
+/// Put rendered shortcodes back in place of their placeholders in the rendered HTML
+///
+/// A shortcode on a line of its own ends up alone in a paragraph, which is dropped so
+/// block markup like `<figure>` is not nested inside `<p>`.
+pub fn restore_components(html: &str, components: &[String]) -> String {
+    let mut output = html.to_string();
+    for (index, component) in components.iter().enumerate() {
+        let placeholder = format!("{PLACEHOLDER_START}{index}{PLACEHOLDER_END}");
+        output = output
+            .replace(&format!("<p>{placeholder}</p>"), component)
+            .replace(&placeholder, component);
+    }
     output
 }
 
@@ -0,0 +1,1097 @@
+use crate::escape::escape_text;
+use log::warn;
+
+/// MathML namespace used on every generated `<math>` element
+const MATHML_NAMESPACE: &str = "http://www.w3.org/1998/Math/MathML";
+
+/// Result of converting a TeX formula to MathML
+#[derive(Debug, Clone)]
+pub struct MathOutput {
+    pub mathml: String,
+    pub diagnostics: Vec<String>,
+}
+
+/// A rendered MathML node and whether its scripts go above/below it in display mode
+struct Atom {
+    markup: String,
+    limits: bool,
+}
+
+impl Atom {
+    fn new(markup: String) -> Self {
+        Self {
+            markup,
+            limits: false,
+        }
+    }
+}
+
+/// Environments that lay out their rows as a `<mtable>`
+const TABLE_ENVIRONMENTS: &[&str] = &[
+    "matrix",
+    "pmatrix",
+    "bmatrix",
+    "Bmatrix",
+    "vmatrix",
+    "Vmatrix",
+    "smallmatrix",
+    "cases",
+    "aligned",
+    "align",
+    "align*",
+    "gathered",
+    "gather",
+    "gather*",
+    "split",
+    "array",
+];
+
+/// Render a TeX formula to MathML, logging every unsupported construct as a warning
+pub fn render_math(tex: &str, display: bool) -> String {
+    let output = convert_tex_to_mathml(tex, display);
+    for diagnostic in &output.diagnostics {
+        warn!("Math: {diagnostic} in `{tex}`");
+    }
+    output.mathml
+}
+
+/// Convert a TeX formula to a MathML `<math>` element
+///
+/// The source TeX is kept as an `application/x-tex` annotation so it survives copy/paste
+/// and is available to assistive technology. Unsupported commands are rendered as
+/// `<merror>` and reported in `diagnostics` instead of producing broken markup.
+pub fn convert_tex_to_mathml(tex: &str, display: bool) -> MathOutput {
+    let mut parser = MathParser::new(tex, display);
+    let body = parser.parse_formula();
+
+    let display_attr = if display { "block" } else { "inline" };
+    let mathml = format!(
+        r#"<math xmlns="{MATHML_NAMESPACE}" display="{display_attr}"><semantics>{body}<annotation encoding="application/x-tex">{}</annotation></semantics></math>"#,
+        escape_text(tex.trim())
+    );
+
+    MathOutput {
+        mathml,
+        diagnostics: parser.diagnostics,
+    }
+}
+
+/// Recursive-descent TeX math parser emitting MathML directly
+struct MathParser {
+    input: Vec<char>,
+    position: usize,
+    display: bool,
+    diagnostics: Vec<String>,
+}
+
+impl MathParser {
+    fn new(input: &str, display: bool) -> Self {
+        Self {
+            input: input.chars().collect(),
+            position: 0,
+            display,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn current_char(&self) -> Option<char> {
+        self.input.get(self.position).copied()
+    }
+
+    fn peek_char(&self, offset: usize) -> Option<char> {
+        self.input.get(self.position + offset).copied()
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.current_char() {
+            if c.is_whitespace() {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.peek_char(i) == Some(c))
+    }
+
+    /// Check for a control word such as `\right` without matching `\rightarrow`
+    fn at_command(&self, name: &str) -> bool {
+        if self.current_char() != Some('\\') {
+            return false;
+        }
+        let len = name.chars().count();
+        self.input[self.position + 1..]
+            .iter()
+            .take(len)
+            .copied()
+            .eq(name.chars())
+            && !self
+                .peek_char(len + 1)
+                .is_some_and(|c| c.is_ascii_alphabetic())
+    }
+
+    fn error(&mut self, message: String, source: &str) -> String {
+        let markup = format!("<merror><mtext>{}</mtext></merror>", escape_text(source));
+        self.diagnostics.push(message);
+        markup
+    }
+
+    /// Parse the whole input, recovering from stray closing tokens at the top level
+    fn parse_formula(&mut self) -> String {
+        let mut items = Vec::new();
+
+        loop {
+            items.extend(self.parse_row());
+            match self.current_char() {
+                None => break,
+                Some('}') => {
+                    self.advance();
+                    items.push(self.error("unmatched `}`".to_string(), "}"));
+                }
+                Some('&') => {
+                    self.advance();
+                    items.push(self.error("`&` outside of an environment".to_string(), "&"));
+                }
+                Some('\\') if self.starts_with("\\\\") => {
+                    // Line breaks only make sense inside environments; ignore them here
+                    self.position += 2;
+                }
+                Some('\\') if self.at_command("right") => {
+                    self.position += "\\right".len();
+                    let delimiter = self.parse_delimiter();
+                    items.push(self.error(
+                        "`\\right` without matching `\\left`".to_string(),
+                        &format!("\\right{delimiter}"),
+                    ));
+                }
+                Some('\\') if self.at_command("end") => {
+                    self.position += "\\end".len();
+                    let name = self.parse_raw_group().unwrap_or_default();
+                    items.push(self.error(
+                        format!("`\\end{{{name}}}` without matching `\\begin`"),
+                        &format!("\\end{{{name}}}"),
+                    ));
+                }
+                Some(_) => break,
+            }
+        }
+
+        wrap_row(items)
+    }
+
+    /// Parse atoms until a closing token (`}`, `&`, `\\`, `\right`, `\end`) or the end
+    fn parse_row(&mut self) -> Vec<String> {
+        let mut items = Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            match self.current_char() {
+                None | Some('}') | Some('&') => break,
+                Some('\\')
+                    if self.starts_with("\\\\")
+                        || self.at_command("right")
+                        || self.at_command("end") =>
+                {
+                    break;
+                }
+                Some(_) => {}
+            }
+
+            let atom = match self.parse_atom() {
+                Some(atom) => atom,
+                None => continue,
+            };
+            let atom = self.parse_scripts(atom);
+            items.push(atom.markup);
+        }
+
+        items
+    }
+
+    /// Attach any `^` / `_` scripts that follow an atom
+    fn parse_scripts(&mut self, base: Atom) -> Atom {
+        let mut subscript = None;
+        let mut superscript = None;
+        let mut limits = base.limits;
+
+        loop {
+            self.skip_whitespace();
+            if self.at_command("limits") || self.at_command("nolimits") {
+                limits = self.at_command("limits");
+                self.advance();
+                let _ = self.parse_command_name();
+                continue;
+            }
+            match self.current_char() {
+                Some('_') if subscript.is_none() => {
+                    self.advance();
+                    subscript = Some(self.parse_argument());
+                }
+                Some('^') if superscript.is_none() => {
+                    self.advance();
+                    superscript = Some(self.parse_argument());
+                }
+                Some('\'') if superscript.is_none() => {
+                    let mut primes = String::new();
+                    while self.current_char() == Some('\'') {
+                        primes.push('′');
+                        self.advance();
+                    }
+                    superscript = Some(format!("<mo>{primes}</mo>"));
+                }
+                Some('_') | Some('^') => {
+                    self.advance();
+                    let _ = self.parse_argument();
+                    let markup = self.error("double script".to_string(), "^");
+                    return Atom::new(format!("<mrow>{}{markup}</mrow>", base.markup));
+                }
+                _ => break,
+            }
+        }
+
+        let under_over = limits && (self.display || base.limits != limits);
+        let markup = match (subscript, superscript) {
+            (None, None) => return base,
+            (Some(sub), None) if under_over => format!("<munder>{}{sub}</munder>", base.markup),
+            (None, Some(sup)) if under_over => format!("<mover>{}{sup}</mover>", base.markup),
+            (Some(sub), Some(sup)) if under_over => {
+                format!("<munderover>{}{sub}{sup}</munderover>", base.markup)
+            }
+            (Some(sub), None) => format!("<msub>{}{sub}</msub>", base.markup),
+            (None, Some(sup)) => format!("<msup>{}{sup}</msup>", base.markup),
+            (Some(sub), Some(sup)) => format!("<msubsup>{}{sub}{sup}</msubsup>", base.markup),
+        };
+
+        Atom::new(markup)
+    }
+
+    /// Parse a single argument: a braced group, a command, or one character
+    fn parse_argument(&mut self) -> String {
+        self.skip_whitespace();
+        match self.current_char() {
+            None => self.error("missing argument".to_string(), ""),
+            Some('{') | Some('\\') => match self.parse_atom() {
+                Some(atom) => atom.markup,
+                None => "<mrow></mrow>".to_string(),
+            },
+            Some(c) if c.is_ascii_digit() => {
+                self.advance();
+                format!("<mn>{c}</mn>")
+            }
+            Some(_) => match self.parse_atom() {
+                Some(atom) => atom.markup,
+                None => "<mrow></mrow>".to_string(),
+            },
+        }
+    }
+
+    /// Parse one atom; returns `None` for constructs that render nothing
+    fn parse_atom(&mut self) -> Option<Atom> {
+        let c = self.current_char()?;
+
+        match c {
+            '{' => {
+                self.advance();
+                let items = self.parse_row();
+                self.expect_closing_brace();
+                Some(Atom::new(wrap_row(items)))
+            }
+            '\\' => self.parse_command(),
+            '^' | '_' => {
+                // Script without a base, e.g. `{}^{14}C` written as `^{14}C`
+                Some(Atom::new("<mrow></mrow>".to_string()))
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && self.peek_char(1).is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                let mut number = String::new();
+                while let Some(n) = self.current_char() {
+                    if n.is_ascii_digit()
+                        || (n == '.' && self.peek_char(1).is_some_and(|d| d.is_ascii_digit()))
+                    {
+                        number.push(n);
+                        self.advance();
+                    } else {
+                        break;
+                    }
+                }
+                Some(Atom::new(format!("<mn>{number}</mn>")))
+            }
+            c if c.is_alphabetic() => {
+                self.advance();
+                Some(Atom::new(format!("<mi>{c}</mi>")))
+            }
+            '~' => {
+                self.advance();
+                Some(Atom::new(
+                    r#"<mspace width="0.3333em"></mspace>"#.to_string(),
+                ))
+            }
+            '-' => {
+                self.advance();
+                Some(Atom::new("<mo>−</mo>".to_string()))
+            }
+            '(' | ')' | '[' | ']' | '|' => {
+                self.advance();
+                Some(Atom::new(format!(r#"<mo stretchy="false">{c}</mo>"#)))
+            }
+            '}' | '&' => None,
+            _ => {
+                self.advance();
+                Some(Atom::new(format!(
+                    "<mo>{}</mo>",
+                    escape_text(&c.to_string())
+                )))
+            }
+        }
+    }
+
+    fn expect_closing_brace(&mut self) {
+        if self.current_char() == Some('}') {
+            self.advance();
+        } else {
+            self.diagnostics.push("missing closing `}`".to_string());
+        }
+    }
+
+    /// Read a command name after the backslash: a run of letters or a single symbol
+    fn parse_command_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.current_char() {
+            if c.is_ascii_alphabetic() {
+                name.push(c);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        if name.is_empty()
+            && let Some(c) = self.current_char()
+        {
+            name.push(c);
+            self.advance();
+        }
+        name
+    }
+
+    /// Read the raw text of a braced group without interpreting it
+    fn parse_raw_group(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if self.current_char() != Some('{') {
+            return None;
+        }
+        self.advance();
+
+        let mut depth = 1;
+        let mut text = String::new();
+        while let Some(c) = self.current_char() {
+            self.advance();
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(text);
+                    }
+                }
+                '\\' => {
+                    // Keep escaped characters such as `\}` intact
+                    text.push(c);
+                    if let Some(next) = self.current_char() {
+                        text.push(next);
+                        self.advance();
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            text.push(c);
+        }
+
+        self.diagnostics.push("missing closing `}`".to_string());
+        Some(text)
+    }
+
+    fn parse_command(&mut self) -> Option<Atom> {
+        self.advance(); // '\'
+        let name = self.parse_command_name();
+
+        if let Some(letter) = greek_letter(&name) {
+            let markup = if letter.is_uppercase() {
+                format!(r#"<mi mathvariant="normal">{letter}</mi>"#)
+            } else {
+                format!("<mi>{letter}</mi>")
+            };
+            return Some(Atom::new(markup));
+        }
+
+        if let Some(symbol) = identifier_symbol(&name) {
+            return Some(Atom::new(format!("<mi>{symbol}</mi>")));
+        }
+
+        if let Some(symbol) = operator_symbol(&name) {
+            return Some(Atom::new(format!("<mo>{symbol}</mo>")));
+        }
+
+        if let Some(symbol) = large_operator(&name) {
+            return Some(Atom {
+                markup: format!("<mo>{symbol}</mo>"),
+                limits: !name.contains("int"),
+            });
+        }
+
+        if let Some(limits) = function_name(&name) {
+            return Some(Atom {
+                markup: format!("<mi>{name}</mi>"),
+                limits,
+            });
+        }
+
+        if let Some(width) = spacing_width(&name) {
+            return Some(Atom::new(format!(r#"<mspace width="{width}"></mspace>"#)));
+        }
+
+        match name.as_str() {
+            "{" | "}" | "%" | "$" | "&" | "#" | "_" => {
+                Some(Atom::new(format!("<mo>{}</mo>", escape_text(&name))))
+            }
+            "|" => Some(Atom::new("<mo>‖</mo>".to_string())),
+            "frac" | "dfrac" | "tfrac" | "cfrac" => {
+                let numerator = self.parse_argument();
+                let denominator = self.parse_argument();
+                let fraction = format!("<mfrac>{numerator}{denominator}</mfrac>");
+                let markup = match name.as_str() {
+                    "dfrac" | "cfrac" => {
+                        format!(
+                            r#"<mstyle displaystyle="true" scriptlevel="0">{fraction}</mstyle>"#
+                        )
+                    }
+                    "tfrac" => format!(r#"<mstyle displaystyle="false">{fraction}</mstyle>"#),
+                    _ => fraction,
+                };
+                Some(Atom::new(markup))
+            }
+            "binom" => {
+                let top = self.parse_argument();
+                let bottom = self.parse_argument();
+                Some(Atom::new(format!(
+                    r#"<mrow><mo>(</mo><mfrac linethickness="0">{top}{bottom}</mfrac><mo>)</mo></mrow>"#
+                )))
+            }
+            "sqrt" => {
+                self.skip_whitespace();
+                let index = if self.current_char() == Some('[') {
+                    self.advance();
+                    let mut items = Vec::new();
+                    while let Some(c) = self.current_char() {
+                        if c == ']' {
+                            break;
+                        }
+                        match self.parse_atom() {
+                            Some(atom) => items.push(self.parse_scripts(atom).markup),
+                            None => self.advance(),
+                        }
+                    }
+                    if self.current_char() == Some(']') {
+                        self.advance();
+                    } else {
+                        self.diagnostics
+                            .push("missing closing `]` in `\\sqrt`".to_string());
+                    }
+                    Some(wrap_row(items))
+                } else {
+                    None
+                };
+                let radicand = self.parse_argument();
+                let markup = match index {
+                    Some(index) => format!("<mroot>{radicand}{index}</mroot>"),
+                    None => format!("<msqrt>{radicand}</msqrt>"),
+                };
+                Some(Atom::new(markup))
+            }
+            "text" | "textrm" | "textit" | "textbf" | "textsf" | "texttt" | "mbox" => {
+                let text = self.parse_raw_group().unwrap_or_default();
+                let variant = match name.as_str() {
+                    "textit" => r#" mathvariant="italic""#,
+                    "textbf" => r#" mathvariant="bold""#,
+                    "textsf" => r#" mathvariant="sans-serif""#,
+                    "texttt" => r#" mathvariant="monospace""#,
+                    _ => "",
+                };
+                Some(Atom::new(format!(
+                    "<mtext{variant}>{}</mtext>",
+                    escape_text(&unescape_text(&text))
+                )))
+            }
+            "operatorname" => {
+                let text = self.parse_raw_group().unwrap_or_default();
+                Some(Atom::new(format!("<mi>{}</mi>", escape_text(text.trim()))))
+            }
+            "mathrm" | "mathit" | "mathbf" | "mathbb" | "mathcal" | "mathfrak" | "mathsf"
+            | "mathtt" | "boldsymbol" | "bm" => {
+                let variant = match name.as_str() {
+                    "mathrm" => "normal",
+                    "mathit" => "italic",
+                    "mathbf" => "bold",
+                    "mathbb" => "double-struck",
+                    "mathcal" => "script",
+                    "mathfrak" => "fraktur",
+                    "mathsf" => "sans-serif",
+                    "mathtt" => "monospace",
+                    _ => "bold-italic",
+                };
+                self.skip_whitespace();
+                if self.current_char() == Some('{') {
+                    let start = self.position;
+                    let text = self.parse_raw_group().unwrap_or_default();
+                    if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric()) {
+                        return Some(Atom::new(format!(
+                            r#"<mi mathvariant="{variant}">{text}</mi>"#
+                        )));
+                    }
+                    self.position = start;
+                }
+                let argument = self.parse_argument();
+                Some(Atom::new(format!(
+                    r#"<mstyle mathvariant="{variant}">{argument}</mstyle>"#
+                )))
+            }
+            "hat" | "widehat" | "bar" | "overline" | "vec" | "overrightarrow" | "dot" | "ddot"
+            | "tilde" | "widetilde" | "check" | "breve" | "acute" | "grave" | "overbrace" => {
+                let (accent, stretchy) = match name.as_str() {
+                    "hat" => ("^", false),
+                    "widehat" => ("^", true),
+                    "bar" => ("¯", false),
+                    "overline" => ("¯", true),
+                    "vec" => ("→", false),
+                    "overrightarrow" => ("→", true),
+                    "dot" => ("˙", false),
+                    "ddot" => ("¨", false),
+                    "tilde" => ("~", false),
+                    "widetilde" => ("~", true),
+                    "check" => ("ˇ", false),
+                    "breve" => ("˘", false),
+                    "acute" => ("´", false),
+                    "grave" => ("`", false),
+                    _ => ("⏞", true),
+                };
+                let base = self.parse_argument();
+                Some(Atom {
+                    markup: format!(
+                        r#"<mover accent="true">{base}<mo stretchy="{stretchy}">{accent}</mo></mover>"#
+                    ),
+                    limits: name == "overbrace",
+                })
+            }
+            "underline" | "underbrace" => {
+                let accent = if name == "underline" { "_" } else { "⏟" };
+                let base = self.parse_argument();
+                Some(Atom {
+                    markup: format!(
+                        r#"<munder accentunder="true">{base}<mo stretchy="true">{accent}</mo></munder>"#
+                    ),
+                    limits: name == "underbrace",
+                })
+            }
+            "left" => Some(Atom::new(self.parse_left_right())),
+            "begin" => Some(Atom::new(self.parse_environment())),
+            "displaystyle" | "textstyle" => {
+                let items = self.parse_row();
+                let display = name == "displaystyle";
+                Some(Atom::new(format!(
+                    r#"<mstyle displaystyle="{display}">{}</mstyle>"#,
+                    wrap_row(items)
+                )))
+            }
+            "nonumber" | "notag" => None,
+            "" => Some(Atom::new(
+                self.error("dangling `\\` at end of formula".to_string(), "\\"),
+            )),
+            _ => {
+                let markup = self.error(
+                    format!("unsupported command `\\{name}`"),
+                    &format!("\\{name}"),
+                );
+                Some(Atom::new(markup))
+            }
+        }
+    }
+
+    /// Parse the delimiter following `\left` or `\right`
+    fn parse_delimiter(&mut self) -> String {
+        self.skip_whitespace();
+        match self.current_char() {
+            Some('\\') => {
+                self.advance();
+                let name = self.parse_command_name();
+                match name.as_str() {
+                    "{" | "}" => name,
+                    "|" | "Vert" => "‖".to_string(),
+                    "vert" => "|".to_string(),
+                    _ => match operator_symbol(&name) {
+                        Some(symbol) => symbol.to_string(),
+                        None => {
+                            self.diagnostics
+                                .push(format!("unsupported delimiter `\\{name}`"));
+                            String::new()
+                        }
+                    },
+                }
+            }
+            Some('.') => {
+                self.advance();
+                String::new()
+            }
+            Some(c) => {
+                self.advance();
+                escape_text(&c.to_string())
+            }
+            None => {
+                self.diagnostics.push("missing delimiter".to_string());
+                String::new()
+            }
+        }
+    }
+
+    fn parse_left_right(&mut self) -> String {
+        let open = self.parse_delimiter();
+        let items = self.parse_row();
+
+        let close = if self.at_command("right") {
+            self.position += "\\right".len();
+            self.parse_delimiter()
+        } else {
+            self.diagnostics
+                .push("`\\left` without matching `\\right`".to_string());
+            String::new()
+        };
+
+        let mut markup = String::from("<mrow>");
+        if !open.is_empty() {
+            markup.push_str(&format!(r#"<mo fence="true" stretchy="true">{open}</mo>"#));
+        }
+        for item in items {
+            markup.push_str(&item);
+        }
+        if !close.is_empty() {
+            markup.push_str(&format!(r#"<mo fence="true" stretchy="true">{close}</mo>"#));
+        }
+        markup.push_str("</mrow>");
+        markup
+    }
+
+    /// Parse `\begin{env} ... \end{env}` into a table
+    fn parse_environment(&mut self) -> String {
+        let name = self.parse_raw_group().unwrap_or_default();
+        if !TABLE_ENVIRONMENTS.contains(&name.as_str()) {
+            let markup = self.error(
+                format!("unsupported environment `{name}`"),
+                &format!("\\begin{{{name}}}"),
+            );
+            self.skip_environment_body(&name);
+            return markup;
+        }
+
+        if name == "array" {
+            // Column specification, e.g. {cc|l}; alignment is not rendered
+            let _ = self.parse_raw_group();
+        }
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        let mut cells = Vec::new();
+
+        loop {
+            let items = self.parse_row();
+            cells.push(wrap_row(items));
+
+            match self.current_char() {
+                Some('&') => {
+                    self.advance();
+                }
+                Some('\\') if self.starts_with("\\\\") => {
+                    self.position += 2;
+                    rows.push(std::mem::take(&mut cells));
+                }
+                Some('\\') if self.at_command("end") => {
+                    self.position += "\\end".len();
+                    let end_name = self.parse_raw_group().unwrap_or_default();
+                    if end_name != name {
+                        self.diagnostics.push(format!(
+                            "`\\begin{{{name}}}` closed by `\\end{{{end_name}}}`"
+                        ));
+                    }
+                    break;
+                }
+                Some('\\') if self.at_command("right") => {
+                    self.diagnostics
+                        .push(format!("`\\right` inside `{name}` environment"));
+                    break;
+                }
+                Some('}') => {
+                    self.advance();
+                    self.diagnostics.push("unmatched `}`".to_string());
+                }
+                _ => {
+                    self.diagnostics.push(format!("missing `\\end{{{name}}}`"));
+                    break;
+                }
+            }
+        }
+
+        // A trailing `\\` leaves an empty last row behind
+        if cells.len() > 1 || cells.first().is_some_and(|cell| cell != "<mrow></mrow>") {
+            rows.push(cells);
+        }
+
+        let column_align = match name.as_str() {
+            "aligned" | "align" | "align*" | "split" => r#" columnalign="right left""#,
+            "cases" => r#" columnalign="left left""#,
+            _ => "",
+        };
+        let display_style = matches!(
+            name.as_str(),
+            "aligned" | "align" | "align*" | "split" | "gathered" | "gather" | "gather*"
+        );
+
+        let mut table = format!(r#"<mtable{column_align}"#);
+        if display_style {
+            table.push_str(r#" displaystyle="true""#);
+        }
+        table.push('>');
+        for row in rows {
+            table.push_str("<mtr>");
+            for cell in row {
+                table.push_str(&format!("<mtd>{cell}</mtd>"));
+            }
+            table.push_str("</mtr>");
+        }
+        table.push_str("</mtable>");
+
+        let (open, close) = match name.as_str() {
+            "pmatrix" => ("(", ")"),
+            "bmatrix" => ("[", "]"),
+            "Bmatrix" => ("{", "}"),
+            "vmatrix" => ("|", "|"),
+            "Vmatrix" => ("‖", "‖"),
+            "cases" => ("{", ""),
+            _ => ("", ""),
+        };
+
+        if open.is_empty() && close.is_empty() {
+            return table;
+        }
+
+        let mut markup = String::from("<mrow>");
+        if !open.is_empty() {
+            markup.push_str(&format!(r#"<mo fence="true" stretchy="true">{open}</mo>"#));
+        }
+        markup.push_str(&table);
+        if !close.is_empty() {
+            markup.push_str(&format!(r#"<mo fence="true" stretchy="true">{close}</mo>"#));
+        }
+        markup.push_str("</mrow>");
+        markup
+    }
+
+    /// Skip to the matching `\end{name}` of an unsupported environment
+    fn skip_environment_body(&mut self, name: &str) {
+        let end = format!("\\end{{{name}}}");
+        while self.position < self.input.len() {
+            if self.starts_with(&end) {
+                self.position += end.chars().count();
+                return;
+            }
+            self.advance();
+        }
+    }
+}
+
+/// Wrap several nodes in an `<mrow>`, leaving a single node untouched
+fn wrap_row(items: Vec<String>) -> String {
+    if items.len() == 1 {
+        items.into_iter().next().unwrap_or_default()
+    } else {
+        format!("<mrow>{}</mrow>", items.concat())
+    }
+}
+
+/// Resolve TeX escapes inside `\text{...}`
+fn unescape_text(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek().is_some_and(|n| "{}%$&#_ ".contains(*n)) {
+            if let Some(next) = chars.next() {
+                output.push(next);
+            }
+        } else {
+            output.push(c);
+        }
+    }
+    output
+}
+
+fn greek_letter(name: &str) -> Option<char> {
+    let letter = match name {
+        "alpha" => 'α',
+        "beta" => 'β',
+        "gamma" => 'γ',
+        "delta" => 'δ',
+        "epsilon" => 'ϵ',
+        "varepsilon" => 'ε',
+        "zeta" => 'ζ',
+        "eta" => 'η',
+        "theta" => 'θ',
+        "vartheta" => 'ϑ',
+        "iota" => 'ι',
+        "kappa" => 'κ',
+        "lambda" => 'λ',
+        "mu" => 'μ',
+        "nu" => 'ν',
+        "xi" => 'ξ',
+        "pi" => 'π',
+        "varpi" => 'ϖ',
+        "rho" => 'ρ',
+        "varrho" => 'ϱ',
+        "sigma" => 'σ',
+        "varsigma" => 'ς',
+        "tau" => 'τ',
+        "upsilon" => 'υ',
+        "phi" => 'ϕ',
+        "varphi" => 'φ',
+        "chi" => 'χ',
+        "psi" => 'ψ',
+        "omega" => 'ω',
+        "Gamma" => 'Γ',
+        "Delta" => 'Δ',
+        "Theta" => 'Θ',
+        "Lambda" => 'Λ',
+        "Xi" => 'Ξ',
+        "Pi" => 'Π',
+        "Sigma" => 'Σ',
+        "Upsilon" => 'Υ',
+        "Phi" => 'Φ',
+        "Psi" => 'Ψ',
+        "Omega" => 'Ω',
+        _ => return None,
+    };
+    Some(letter)
+}
+
+/// Symbols that behave like identifiers rather than operators
+fn identifier_symbol(name: &str) -> Option<&'static str> {
+    let symbol = match name {
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "emptyset" | "varnothing" => "∅",
+        "ell" => "ℓ",
+        "hbar" => "ℏ",
+        "aleph" => "ℵ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "wp" => "℘",
+        "top" => "⊤",
+        "bot" => "⊥",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn operator_symbol(name: &str) -> Option<&'static str> {
+    let symbol = match name {
+        "cdot" => "⋅",
+        "times" => "×",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "ast" => "∗",
+        "star" => "⋆",
+        "circ" => "∘",
+        "bullet" => "∙",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "ll" => "≪",
+        "gg" => "≫",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "longrightarrow" => "⟶",
+        "longmapsto" => "⟼",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "oplus" => "⊕",
+        "ominus" => "⊖",
+        "otimes" => "⊗",
+        "odot" => "⊙",
+        "perp" => "⊥",
+        "parallel" => "∥",
+        "mid" => "∣",
+        "vdash" => "⊢",
+        "models" => "⊨",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "vert" => "|",
+        "Vert" => "‖",
+        "prime" => "′",
+        "colon" => ":",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "lbrack" => "[",
+        "rbrack" => "]",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+fn large_operator(name: &str) -> Option<&'static str> {
+    let symbol = match name {
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "bigvee" => "⋁",
+        "bigwedge" => "⋀",
+        "bigoplus" => "⨁",
+        "bigotimes" => "⨂",
+        _ => return None,
+    };
+    Some(symbol)
+}
+
+/// Upright function names; returns whether they take limits in display mode
+fn function_name(name: &str) -> Option<bool> {
+    match name {
+        "lim" | "limsup" | "liminf" | "max" | "min" | "sup" | "inf" | "det" | "gcd" | "Pr"
+        | "argmax" | "argmin" => Some(true),
+        "sin" | "cos" | "tan" | "cot" | "sec" | "csc" | "arcsin" | "arccos" | "arctan" | "sinh"
+        | "cosh" | "tanh" | "log" | "ln" | "lg" | "exp" | "deg" | "dim" | "ker" | "arg" | "hom"
+        | "mod" | "bmod" => Some(false),
+        _ => None,
+    }
+}
+
+fn spacing_width(name: &str) -> Option<&'static str> {
+    let width = match name {
+        "," | "thinspace" => "0.1667em",
+        ":" | ">" | "medspace" => "0.2222em",
+        ";" | "thickspace" => "0.2778em",
+        "!" | "negthinspace" => "-0.1667em",
+        " " => "0.3333em",
+        "quad" => "1em",
+        "qquad" => "2em",
+        _ => return None,
+    };
+    Some(width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_math_keeps_tex_annotation() {
+        let output = convert_tex_to_mathml("x^2 + y_i", false);
+
+        assert!(output.diagnostics.is_empty());
+        assert!(output.mathml.contains(r#"display="inline""#));
+        assert!(output.mathml.contains("<msup><mi>x</mi><mn>2</mn></msup>"));
+        assert!(output.mathml.contains("<msub><mi>y</mi><mi>i</mi></msub>"));
+        assert!(
+            output
+                .mathml
+                .contains(r#"<annotation encoding="application/x-tex">x^2 + y_i</annotation>"#)
+        );
+    }
+
+    #[test]
+    fn test_display_math_structures() {
+        let output = convert_tex_to_mathml(
+            r"\sum_{i=1}^{n} \frac{1}{\sqrt{i}} \leq \left( \alpha \right)",
+            true,
+        );
+
+        assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        assert!(output.mathml.contains(r#"display="block""#));
+        assert!(output.mathml.contains("<munderover><mo>∑</mo>"));
+        assert!(
+            output
+                .mathml
+                .contains("<mfrac><mn>1</mn><msqrt><mi>i</mi></msqrt></mfrac>")
+        );
+        assert!(output.mathml.contains("<mo>≤</mo>"));
+        assert!(output.mathml.contains("<mi>α</mi>"));
+    }
+
+    #[test]
+    fn test_matrix_environment() {
+        let output = convert_tex_to_mathml(r"\begin{pmatrix} a & b \\ c & d \end{pmatrix}", true);
+
+        assert!(output.diagnostics.is_empty(), "{:?}", output.diagnostics);
+        assert_eq!(output.mathml.matches("<mtr>").count(), 2);
+        assert_eq!(output.mathml.matches("<mtd>").count(), 4);
+    }
+
+    #[test]
+    fn test_unsupported_command_reports_diagnostic() {
+        let output = convert_tex_to_mathml(r"a \frobnicate{b} < c", false);
+
+        assert_eq!(output.diagnostics.len(), 1);
+        assert!(output.diagnostics[0].contains("\\frobnicate"));
+        assert!(
+            output
+                .mathml
+                .contains("<merror><mtext>\\frobnicate</mtext></merror>")
+        );
+        assert!(output.mathml.contains("<mo>&lt;</mo>"));
+        assert!(!output.mathml.contains("< c"));
+    }
+}
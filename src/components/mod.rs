@@ -1,6 +1,7 @@
-// pub mod code_block;
 pub mod codeblock;
 pub mod custom_components;
+pub mod math;
+pub mod og_image;
 pub mod rss;
 pub mod search;
 pub mod sitemap;
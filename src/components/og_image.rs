@@ -0,0 +1,499 @@
+//! Open Graph share cards for blog posts
+//!
+//! Each post gets a 1200×630 card with the site logo, its title, date and tags. The
+//! card is laid out as SVG and rasterized to PNG with resvg, so no image tools or
+//! browser are needed. PNGs are cached per post by a hash of the SVG, which changes
+//! whenever anything drawn on the card does.
+
+use crate::assets::AssetManifest;
+use crate::blog::BlogPost;
+use crate::escape::{escape_attr, escape_text};
+use crate::hash::fnv1a;
+use crate::page_meta::SITE_NAME;
+use log::{debug, warn};
+use resvg::{tiny_skia, usvg};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Card size recommended by Open Graph and Twitter
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+/// Logo drawn in the corner of every card, relative to the static directory
+pub const LOGO_PATH: &str = "assets/favicon/android-chrome-512x512.png";
+
+/// Stylesheet whose custom properties give the card colors, relative to the static directory
+pub const PALETTE_STYLESHEET: &str = "css/style.css";
+
+/// Custom properties read for each card color, in the order of [`CardTheme::colors_mut`]
+const PALETTE_PROPERTIES: [&str; 7] = [
+    "--bg-primary",
+    "--bg-secondary",
+    "--primary-color",
+    "--secondary-color",
+    "--accent-color",
+    "--text-primary",
+    "--text-muted",
+];
+
+/// Space between the card edge and its content
+const MARGIN: u32 = 80;
+
+/// Fonts tried in order; the first one installed is used
+const FONT_FAMILY: &str = "Inter, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, 'DejaVu Sans', 'Liberation Sans', sans-serif";
+
+/// Title font sizes tried from largest to smallest until the title fits
+const TITLE_SIZES: [u32; 3] = [72, 60, 50];
+
+/// Lines of title a card has room for
+const TITLE_MAX_LINES: usize = 3;
+
+/// Average glyph width of a bold sans-serif font, as a fraction of the font size
+const GLYPH_WIDTH: f32 = 0.62;
+
+/// Colors of a card, matching the dark variant of the default site palette
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardTheme {
+    pub background: String,
+    pub background_end: String,
+    pub primary: String,
+    pub secondary: String,
+    pub accent: String,
+    pub text: String,
+    pub muted: String,
+}
+
+impl Default for CardTheme {
+    fn default() -> Self {
+        Self {
+            background: "#0a0a0f".to_string(),
+            background_end: "#1a1a2e".to_string(),
+            primary: "#6667ab".to_string(),
+            secondary: "#8b8cc7".to_string(),
+            accent: "#edbedc".to_string(),
+            text: "#ffffff".to_string(),
+            muted: "#94a3b8".to_string(),
+        }
+    }
+}
+
+impl CardTheme {
+    /// Colors from the custom properties of the site stylesheet
+    ///
+    /// The last declaration of each property wins, which is the dark variant in the
+    /// default stylesheet. Properties that are missing or not plain colors keep the
+    /// default color.
+    pub fn from_assets(assets: &AssetManifest) -> Self {
+        let mut theme = Self::default();
+        if let Some(css) = assets.stylesheet(PALETTE_STYLESHEET) {
+            for (color, property) in theme.colors_mut().into_iter().zip(PALETTE_PROPERTIES) {
+                if let Some(value) = last_declaration(&css, property)
+                    && is_color(value)
+                {
+                    *color = value.to_string();
+                }
+            }
+        }
+        theme
+    }
+
+    fn colors_mut(&mut self) -> [&mut String; 7] {
+        [
+            &mut self.background,
+            &mut self.background_end,
+            &mut self.primary,
+            &mut self.secondary,
+            &mut self.accent,
+            &mut self.text,
+            &mut self.muted,
+        ]
+    }
+}
+
+/// Value of the last declaration of a custom property in a stylesheet
+fn last_declaration<'a>(css: &'a str, property: &str) -> Option<&'a str> {
+    css.match_indices(property)
+        .filter_map(|(start, _)| {
+            // Skip longer names that end in this one, like `--x--bg-primary`
+            let before = css[..start].chars().next_back();
+            if before.is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return None;
+            }
+            let rest = css[start + property.len()..]
+                .trim_start()
+                .strip_prefix(':')?;
+            let end = rest.find([';', '}']).unwrap_or(rest.len());
+            Some(rest[..end].trim())
+        })
+        .last()
+}
+
+/// Whether a CSS value is a color SVG understands without any context
+fn is_color(value: &str) -> bool {
+    match value.strip_prefix('#') {
+        Some(hex) => {
+            matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => {
+            ["rgb(", "rgba(", "hsl(", "hsla("]
+                .iter()
+                .any(|function| value.starts_with(function))
+                || value.chars().all(|c| c.is_ascii_alphabetic())
+                    && !value.is_empty()
+                    && !value.eq_ignore_ascii_case("inherit")
+                    && !value.eq_ignore_ascii_case("currentcolor")
+        }
+    }
+}
+
+/// Lay out the card for a post as SVG
+///
+/// `logo` is the base64 PNG from [`logo`], embedded as the site logo; without it the site
+/// initial is drawn instead.
+pub fn card_svg(post: &BlogPost, logo: Option<&str>, theme: &CardTheme) -> String {
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">
+<defs>
+<linearGradient id="bg" x1="0" y1="0" x2="1" y2="1"><stop offset="0" stop-color="{bg}"/><stop offset="1" stop-color="{bg_end}"/></linearGradient>
+<linearGradient id="bar" x1="0" y1="0" x2="1" y2="0"><stop offset="0" stop-color="{primary}"/><stop offset="0.5" stop-color="{secondary}"/><stop offset="1" stop-color="{accent}"/></linearGradient>
+</defs>
+<rect width="{WIDTH}" height="{HEIGHT}" fill="url(#bg)"/>
+<rect width="{WIDTH}" height="12" fill="url(#bar)"/>
+"#,
+        bg = escape_attr(&theme.background),
+        bg_end = escape_attr(&theme.background_end),
+        primary = escape_attr(&theme.primary),
+        secondary = escape_attr(&theme.secondary),
+        accent = escape_attr(&theme.accent),
+    );
+
+    // Logo and site name
+    match logo {
+        Some(logo) => {
+            let _ = writeln!(
+                svg,
+                r#"<image x="{MARGIN}" y="64" width="72" height="72" href="data:image/png;base64,{logo}"/>"#
+            );
+        }
+        None => {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{MARGIN}" y="64" width="72" height="72" rx="16" fill="{}"/>
+<text x="{}" y="114" font-family="{FONT_FAMILY}" font-size="40" font-weight="bold" fill="{}" text-anchor="middle">{}</text>"#,
+                escape_attr(&theme.primary),
+                MARGIN + 36,
+                escape_attr(&theme.text),
+                escape_text(&SITE_NAME.chars().take(1).collect::<String>()),
+            );
+        }
+    }
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="112" font-family="{FONT_FAMILY}" font-size="36" font-weight="bold" fill="{}">{}</text>"#,
+        MARGIN + 96,
+        escape_attr(&theme.text),
+        escape_text(SITE_NAME)
+    );
+
+    // Title, wrapped to the card width
+    let (size, lines) = wrap_title(post.title());
+    let line_height = size * 6 / 5;
+    let _ = write!(
+        svg,
+        r#"<text font-family="{FONT_FAMILY}" font-size="{size}" font-weight="bold" fill="{}">"#,
+        escape_attr(&theme.text)
+    );
+    for (i, line) in lines.iter().enumerate() {
+        let y = 250 + line_height * i as u32;
+        let _ = write!(
+            svg,
+            r#"<tspan x="{MARGIN}" y="{y}">{}</tspan>"#,
+            escape_text(line)
+        );
+    }
+    svg.push_str("</text>\n");
+
+    // Tags as pills, as many as fit on one row
+    let mut x = MARGIN;
+    let mut tags = post.tags().to_vec();
+    tags.sort();
+    for tag in &tags {
+        let label = format!("#{tag}");
+        let width = text_width(&label, 26) + 40;
+        if x + width > WIDTH - MARGIN {
+            break;
+        }
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x}" y="470" width="{width}" height="48" rx="24" fill="{}" fill-opacity="0.25" stroke="{}"/>
+<text x="{}" y="503" font-family="{FONT_FAMILY}" font-size="26" fill="{}">{}</text>"#,
+            escape_attr(&theme.primary),
+            escape_attr(&theme.secondary),
+            x + 20,
+            escape_attr(&theme.accent),
+            escape_text(&label)
+        );
+        x += width + 16;
+    }
+
+    // Date and author
+    let _ = writeln!(
+        svg,
+        r#"<text x="{MARGIN}" y="575" font-family="{FONT_FAMILY}" font-size="28" fill="{}">{} · {}</text>"#,
+        escape_attr(&theme.muted),
+        post.meta.published_at.format("%B %d, %Y"),
+        escape_text(post.author())
+    );
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Rasterize a card to PNG
+pub fn render_png(svg: &str) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let options = usvg::Options {
+        fontdb: fonts(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options)?;
+    let mut pixmap = tiny_skia::Pixmap::new(WIDTH, HEIGHT).ok_or("Failed to allocate card")?;
+    resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap.encode_png()?)
+}
+
+/// System fonts, loaded once
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    Arc::clone(FONTS.get_or_init(|| {
+        let mut db = usvg::fontdb::Database::new();
+        db.load_system_fonts();
+        if db.is_empty() {
+            warn!("No system fonts found, share cards will be rendered without text");
+        } else {
+            debug!("Loaded {} font faces for share cards", db.len());
+        }
+        Arc::new(db)
+    }))
+}
+
+/// The site logo at [`LOGO_PATH`] under `static_dir`, base64-encoded
+///
+/// The logo is cached under its fingerprint in `assets`, so a replaced logo is read again
+/// once the manifest has been rebuilt.
+pub fn logo(static_dir: &Path, assets: &AssetManifest) -> Option<Arc<str>> {
+    static LOGO: Mutex<Option<(String, Option<Arc<str>>)>> = Mutex::new(None);
+    let Some(fingerprint) = assets.fingerprint(LOGO_PATH) else {
+        debug!("No share card logo at {LOGO_PATH}");
+        return None;
+    };
+
+    let mut cached = LOGO.lock().unwrap();
+    if let Some((key, logo)) = cached.as_ref()
+        && key == fingerprint
+    {
+        return logo.clone();
+    }
+    let path = static_dir.join(LOGO_PATH);
+    let logo: Option<Arc<str>> = std::fs::read(&path)
+        .inspect_err(|e| warn!("Failed to read share card logo {path:?}: {e}"))
+        .ok()
+        .map(|png| base64(&png).into());
+    *cached = Some((fingerprint.to_string(), logo.clone()));
+    logo
+}
+
+/// A rendered card
+#[derive(Debug, Clone)]
+pub struct Card {
+    pub png: Arc<Vec<u8>>,
+    /// Hash of the SVG the card was rendered from, usable as an ETag
+    pub hash: u64,
+}
+
+/// Rendered cards by post slug
+#[derive(Default)]
+pub struct CardCache {
+    cards: Mutex<HashMap<String, Card>>,
+}
+
+impl CardCache {
+    /// Get a post's card, rendering it only if the card's content changed
+    pub fn get_or_render(
+        &self,
+        slug: &str,
+        svg: &str,
+    ) -> Result<Card, Box<dyn std::error::Error + Send + Sync>> {
+        let hash = fnv1a(svg.as_bytes());
+        if let Some(card) = self.cards.lock().unwrap().get(slug)
+            && card.hash == hash
+        {
+            return Ok(card.clone());
+        }
+
+        debug!("Rendering share card for {slug}");
+        let card = Card {
+            png: Arc::new(render_png(svg)?),
+            hash,
+        };
+        self.cards
+            .lock()
+            .unwrap()
+            .insert(slug.to_string(), card.clone());
+        Ok(card)
+    }
+
+    /// Drop the cards of posts that no longer exist
+    #[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+    pub fn remove(&self, slugs: &[String]) {
+        let mut cards = self.cards.lock().unwrap();
+        for slug in slugs {
+            cards.remove(slug);
+        }
+    }
+}
+
+/// Split a title into lines that fit the card, picking the largest font size that fits
+///
+/// Titles too long for the smallest size are cut off with an ellipsis.
+fn wrap_title(title: &str) -> (u32, Vec<String>) {
+    for size in TITLE_SIZES {
+        let lines = wrap(title, max_chars(size));
+        if lines.len() <= TITLE_MAX_LINES {
+            return (size, lines);
+        }
+    }
+
+    let size = TITLE_SIZES[TITLE_SIZES.len() - 1];
+    let mut lines = wrap(title, max_chars(size));
+    lines.truncate(TITLE_MAX_LINES);
+    if let Some(last) = lines.last_mut() {
+        let keep = max_chars(size).saturating_sub(1);
+        *last = format!(
+            "{}…",
+            last.chars().take(keep).collect::<String>().trim_end()
+        );
+    }
+    (size, lines)
+}
+
+/// Characters of title that fit on one line at a font size
+fn max_chars(size: u32) -> usize {
+    ((WIDTH - 2 * MARGIN) as f32 / (size as f32 * GLYPH_WIDTH)) as usize
+}
+
+/// Estimated width of text at a font size
+fn text_width(text: &str, size: u32) -> u32 {
+    (text.chars().count() as f32 * size as f32 * GLYPH_WIDTH) as u32
+}
+
+/// Greedy word wrap; words longer than a line are split
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        while word.len() > max_chars {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..max_chars).collect());
+        }
+        let word: String = word.into_iter().collect();
+        if line.is_empty() {
+            line = word;
+        } else if line.chars().count() + 1 + word.chars().count() <= max_chars {
+            line.push(' ');
+            line.push_str(&word);
+        } else {
+            lines.push(std::mem::replace(&mut line, word));
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Standard base64 with padding, for embedding the logo
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_title_and_encoding() {
+        let (size, lines) = wrap_title("Short title");
+        assert_eq!((size, lines), (72, vec!["Short title".to_string()]));
+
+        let long = "word ".repeat(60);
+        let (size, lines) = wrap_title(&long);
+        assert_eq!(size, 50);
+        assert_eq!(lines.len(), TITLE_MAX_LINES);
+        assert!(
+            lines
+                .iter()
+                .all(|line| line.chars().count() <= max_chars(size))
+        );
+        assert!(lines[2].ends_with('…'));
+
+        assert_eq!(wrap("abcdefgh ij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(base64(b"Ma"), "TWE=");
+        assert_eq!(base64(b"Man!"), "TWFuIQ==");
+    }
+
+    #[test]
+    fn test_theme_and_logo_follow_assets() {
+        let dir = std::env::temp_dir().join(format!("nornity-card-{}", std::process::id()));
+        let logo_path = dir.join(LOGO_PATH);
+        std::fs::create_dir_all(logo_path.parent().unwrap()).unwrap();
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(
+            dir.join(PALETTE_STYLESHEET),
+            ":root { --bg-primary: #fff; --primary-color: #123456; --text-muted: var(--x) }\n\
+             @media (prefers-color-scheme: dark) { :root { --bg-primary: #000000 } }\n",
+        )
+        .unwrap();
+        std::fs::write(&logo_path, b"Ma").unwrap();
+
+        let assets = AssetManifest::build(&dir, true).unwrap();
+        let theme = CardTheme::from_assets(&assets);
+        assert_eq!(theme.background, "#000000");
+        assert_eq!(theme.primary, "#123456");
+        assert_eq!(theme.muted, CardTheme::default().muted);
+        assert_eq!(logo(&dir, &assets).as_deref(), Some("TWE="));
+
+        // A replaced logo is read again once the manifest is rebuilt
+        std::fs::write(&logo_path, b"Man!").unwrap();
+        assert_eq!(logo(&dir, &assets).as_deref(), Some("TWE="));
+        let assets = AssetManifest::build(&dir, true).unwrap();
+        assert_eq!(logo(&dir, &assets).as_deref(), Some("TWFuIQ=="));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            CardTheme::from_assets(&AssetManifest::default()),
+            CardTheme::default()
+        );
+    }
+}
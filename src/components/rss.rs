@@ -19,6 +19,7 @@ pub struct RSSGenerator {
     base_url: String,
     site_title: String,
     site_description: String,
+    #[allow(dead_code)]
     site_author: String,
 }
 
@@ -45,8 +46,12 @@ impl RSSGenerator {
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         info!("Generating RSS feed for: {}", self.base_url);
 
-        let posts = blog_store.get_all_posts();
-        let published_posts: Vec<_> = posts.iter().filter(|post| post.is_published()).collect();
+        let snapshot = blog_store.snapshot();
+        let published_posts: Vec<_> = snapshot
+            .posts_by_date()
+            .iter()
+            .filter(|post| post.is_published())
+            .collect();
 
         info!(
             "Adding {} published blog posts to RSS feed",
@@ -9,48 +9,21 @@ pub struct SearchConfig {
 }
 
 /// Sort options for search results
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum SortOption {
+    #[default]
     DateNewest,
     DateOldest,
     Title,
     Relevance,
 }
 
-impl Default for SortOption {
-    fn default() -> Self {
-        Self::DateNewest
-    }
-}
-
-/// Search results with metadata
-#[derive(Debug, Clone)]
-pub struct SearchResults {
-    pub posts: Vec<SearchResult>,
-    pub total_count: usize,
-    pub filtered_count: usize,
-    pub search_term: Option<String>,
-    pub selected_tags: Vec<String>,
-}
-
-/// Individual search result
-#[derive(Debug, Clone)]
-pub struct SearchResult {
-    pub slug: String,
-    pub title: String,
-    pub excerpt: String,
-    pub date: String,
-    pub iso_date: String,
-    pub author: String,
-    pub tags: Vec<String>,
-    pub relevance_score: f32,
-}
-
 /// Search component for handling server-side search and filtering
 pub struct SearchComponent;
 
 impl SearchComponent {
     /// Parse search parameters from query string
+    #[allow(dead_code)]
     pub fn parse_params(params: &HashMap<String, String>) -> SearchConfig {
         let search_term = params
             .get("search")
@@ -99,15 +72,11 @@ impl SearchComponent {
                 let value = value.trim();
 
                 match key {
-                    "search" => {
-                        if !value.is_empty() {
-                            search_term = Some(value.to_string());
-                        }
+                    "search" if !value.is_empty() => {
+                        search_term = Some(value.to_string());
                     }
-                    "tag" => {
-                        if !value.is_empty() && !selected_tags.contains(&value.to_string()) {
-                            selected_tags.push(value.to_string());
-                        }
+                    "tag" if !value.is_empty() && !selected_tags.contains(&value.to_string()) => {
+                        selected_tags.push(value.to_string());
                     }
                     "sort" => {
                         sort_by = match value {
@@ -129,119 +98,17 @@ impl SearchComponent {
         }
     }
 
-    /// Generate search form HTML
-    pub fn render_search_form(config: &SearchConfig) -> String {
-        let search_value = config.search_term.as_deref().unwrap_or("");
-
-        format!(
-            r#"
-            <form method="GET" action="/blog" class="search-form">
-                <div class="search-container">
-                    <button type="submit" class="search-submit" aria-label="Submit search">
-                        <svg viewBox="0 0 24 24" fill="none" xmlns="http://www.w3.org/2000/svg">
-                            <path d="M21 21L16.514 16.506L21 21ZM19 10.5C19 15.194 15.194 19 10.5 19C5.806 19 2 15.194 2 10.5C2 5.806 5.806 2 10.5 2C15.194 2 19 5.806 19 10.5Z" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"/>
-                        </svg>
-                    </button>
-                    <input 
-                        type="text" 
-                        name="search" 
-                        class="search-input" 
-                        placeholder="Search posts..." 
-                        value="{}"
-                        aria-label="Search blog posts"
-                    >
-                </div>
-                
-                {}
-            </form>
-            "#,
-            html_escape(search_value),
-            {
-                let mut hidden_inputs = String::new();
-                for tag in &config.selected_tags {
-                    hidden_inputs.push_str(&format!(
-                        r#"<input type="hidden" name="tag" value="{}">"#,
-                        html_escape(tag)
-                    ));
-                }
-                hidden_inputs
-            }
-        )
-    }
-
-    /// Generate tag cloud HTML with active state
-    pub fn render_tag_cloud(all_tags: &[String], selected_tags: &[String]) -> String {
-        let mut sorted_tags = all_tags.to_vec();
-        sorted_tags.sort();
-
-        let tag_links = sorted_tags
-            .iter()
-            .map(|tag_name| {
-                let is_active = selected_tags.contains(tag_name);
-                let active_class = if is_active { " active" } else { "" };
-                if is_active {
-                    // If this tag is active, remove it from URL
-                    format!(
-                        r##"<a href="{}" class="tag-link{active_class}" aria-label="Remove tag filter: {}">{}<span class="remove-icon">✕</span></a>"##,
-                        Self::build_url_without_tag(selected_tags, tag_name),
-                        tag_name, tag_name
-                    )
-                } else {
-                    // Add this tag to URL - ensure we don't add duplicates
-                    format!(
-                        r##"<a href="{}" class="tag-link{active_class}" aria-label="Filter by tag: {}">{}</a>"##,
-                        Self::build_url_with_tag(selected_tags, tag_name),
-                        tag_name, tag_name
-                    )
-                }
-            })
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        format!(
-            r#"
-            <div class="tag-cloud" role="region" aria-label="Blog post tags">
-                {}
-            </div>
-            "#,
-            tag_links
-        )
-    }
-
-    /// Generate search results summary
-    pub fn render_search_summary(results: &SearchResults) -> String {
-        let mut summary_parts = Vec::new();
-
-        if let Some(search_term) = &results.search_term {
-            summary_parts.push(format!("search for '{}'", search_term));
-        }
-
-        if !results.selected_tags.is_empty() {
-            if results.selected_tags.len() == 1 {
-                summary_parts.push(format!("tagged '{}'", results.selected_tags[0]));
-            } else {
-                let tags_text = results.selected_tags.join("', '");
-                summary_parts.push(format!("tagged '{}'", tags_text));
-            }
-        }
-
-        let filter_text = if summary_parts.is_empty() {
-            "all posts".to_string()
+    /// URL of the blog list with `tag` toggled in or out of the selected tags
+    pub fn toggle_tag_url(selected_tags: &[String], tag: &str) -> String {
+        if selected_tags.iter().any(|selected| selected == tag) {
+            Self::build_url_without_tag(selected_tags, tag)
         } else {
-            summary_parts.join(" and ")
-        };
-
-        format!(
-            r#"
-            <div class="search-summary" role="status" aria-live="polite">
-                <p>Showing {} of {} posts ({})</p>
-            </div>
-            "#,
-            results.filtered_count, results.total_count, filter_text
-        )
+            Self::build_url_with_tag(selected_tags, tag)
+        }
     }
 
     /// Generate pagination controls
+    #[allow(dead_code)]
     pub fn render_pagination(
         current_page: usize,
         total_pages: usize,
@@ -301,6 +168,7 @@ impl SearchComponent {
     }
 
     /// Build URL for pagination with current search parameters
+    #[allow(dead_code)]
     fn build_pagination_url(page: usize, config: &SearchConfig) -> String {
         let mut params = Vec::new();
 
@@ -371,16 +239,6 @@ impl SearchComponent {
     }
 }
 
-/// Simple HTML escaping function
-fn html_escape(input: &str) -> String {
-    input
-        .replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace(">", "&gt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#39;")
-}
-
 /// Simple URL encoding function
 fn url_encode(input: &str) -> String {
     input
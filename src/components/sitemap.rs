@@ -13,6 +13,7 @@ pub struct SitemapEntry {
 
 /// Change frequency for sitemap entries
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub enum ChangeFrequency {
     Always,
     Hourly,
@@ -105,8 +106,12 @@ impl SitemapGenerator {
     fn add_blog_posts(&self, entries: &mut Vec<SitemapEntry>, blog_store: &BlogStore) {
         debug!("Adding blog posts to sitemap");
 
-        let posts = blog_store.get_all_posts();
-        let published_posts: Vec<_> = posts.iter().filter(|post| post.is_published()).collect();
+        let snapshot = blog_store.snapshot();
+        let published_posts: Vec<_> = snapshot
+            .posts_by_date()
+            .iter()
+            .filter(|post| post.is_published())
+            .collect();
 
         info!(
             "Adding {} published blog posts to sitemap",
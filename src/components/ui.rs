@@ -1,3 +1,4 @@
+use crate::escape::{escape_attr, escape_text};
 use std::collections::HashMap;
 
 /// Render a responsive image with optional caption
@@ -17,13 +18,17 @@ pub fn render_responsive_image(attributes: &HashMap<String, String>) -> String {
 
     let img = format!(
         r#"<img src="{}" alt="{}" loading="{}" decoding="{}" />"#,
-        src, alt, loading, decoding
+        escape_attr(&src),
+        escape_attr(&alt),
+        escape_attr(&loading),
+        escape_attr(&decoding)
     );
 
     match caption {
         Some(c) if !c.trim().is_empty() => format!(
             r#"<figure class="image-figure">{}<figcaption>{}</figcaption></figure>"#,
-            img, c
+            img,
+            escape_text(&c)
         ),
         _ => format!(r#"<figure class="image-figure">{}</figure>"#, img),
     }
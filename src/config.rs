@@ -1,7 +1,8 @@
+use log::warn;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Application configuration
 ///
@@ -12,6 +13,11 @@ use std::path::PathBuf;
 /// static_dir = "static"
 /// content_dir = "content"
 /// base_url = "https://nornity.com"
+/// mode = "production"  # or "development"
+/// author = "Nornity"   # default author for `nornity new`
+/// drafts = false       # serve draft posts
+/// theme = "minimal"    # use templates from themes/minimal/ over the defaults
+/// allowed_html = "iframe[src width height], video[src controls]"  # extra raw HTML allowed in posts
 ///
 /// Comments (# or //) and blank lines are ignored.
 #[derive(Debug, Clone)]
@@ -21,6 +27,45 @@ pub struct Config {
     pub static_dir: String,
     pub content_dir: PathBuf,
     pub base_url: String,
+    pub mode: Mode,
+    pub author: String,
+    pub drafts: bool,
+    /// Installed theme under `themes/` whose templates override the defaults
+    pub theme: Option<String>,
+    /// Tags and attributes allowed in raw post HTML on top of the defaults
+    pub allowed_html: String,
+    /// Config file the settings were read from, if any
+    pub source: Option<PathBuf>,
+    /// Command-line overrides, kept so a reload applies them again
+    pub overrides: Overrides,
+}
+
+/// Settings given on the command line, applied on top of the file and environment
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    pub config_file: Option<PathBuf>,
+    pub host: Option<[u8; 4]>,
+    pub port: Option<u16>,
+    pub drafts: bool,
+}
+
+/// Whether the server runs for authors (development) or readers (production)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    Production,
+    Development,
+}
+
+impl Mode {
+    /// Parse a mode name, accepting the short forms as well
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "production" | "prod" => Some(Mode::Production),
+            "development" | "dev" => Some(Mode::Development),
+            _ => None,
+        }
+    }
 }
 
 impl Default for Config {
@@ -31,75 +76,159 @@ impl Default for Config {
             static_dir: "static".to_string(),
             content_dir: PathBuf::from("content"),
             base_url: "https://nornity.com".to_string(),
+            mode: Mode::default(),
+            author: "Nornity".to_string(),
+            drafts: false,
+            theme: None,
+            allowed_html: String::new(),
+            source: None,
+            overrides: Overrides::default(),
         }
     }
 }
 
+/// Config files tried in order; the first one found wins
+pub const CONFIG_FILES: [&str; 2] = ["config.toml", "config.conf"];
+
 impl Config {
     /// Load config from file (config.toml or config.conf), env, or defaults
     pub fn from_file_or_env() -> Self {
+        Self::load(Overrides::default()).unwrap_or_else(|e| {
+            warn!("{e}, using defaults");
+            let mut config = Config::default();
+            config.apply_env();
+            config
+        })
+    }
+
+    /// Load config from the given or default config file, env, and command-line overrides
+    ///
+    /// Fails if the config file cannot be read.
+    pub fn load(overrides: Overrides) -> Result<Self, String> {
         let mut config = Config::default();
-        // Try config.toml, then config.conf
-        let paths = ["config.toml", "config.conf"];
-        for path in &paths {
-            if let Ok(file) = File::open(path) {
-                let reader = BufReader::new(file);
-                for line in reader.lines().map_while(Result::ok) {
-                    let line = line.trim();
-                    if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
-                        continue;
+
+        let source = match &overrides.config_file {
+            Some(path) if !path.is_file() => {
+                return Err(format!("Config file not found: {}", path.display()));
+            }
+            Some(path) => Some(path.clone()),
+            None => Self::config_file_path(),
+        };
+        if let Some(path) = &source {
+            config
+                .apply_file(path)
+                .map_err(|e| format!("Failed to read config file {}: {e}", path.display()))?;
+        }
+        config.apply_env();
+
+        if let Some(host) = overrides.host {
+            config.host = host;
+        }
+        if let Some(port) = overrides.port {
+            config.port = port;
+        }
+        if overrides.drafts {
+            config.drafts = true;
+        }
+
+        config.source = source;
+        config.overrides = overrides;
+        Ok(config)
+    }
+
+    /// Apply key=value settings from a config file
+    fn apply_file(&mut self, path: &Path) -> std::io::Result<()> {
+        let reader = BufReader::new(File::open(path)?);
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim();
+                let value = value.trim().trim_matches('"');
+                match key {
+                    "host" => {
+                        if let Some(parsed) = parse_host(value) {
+                            self.host = parsed;
+                        }
                     }
-                    if let Some((key, value)) = line.split_once('=') {
-                        let key = key.trim();
-                        let value = value.trim().trim_matches('"');
-                        match key {
-                            "host" => {
-                                if let Some(parsed) = parse_host(value) {
-                                    config.host = parsed;
-                                }
-                            }
-                            "port" => {
-                                if let Ok(port) = value.parse() {
-                                    config.port = port;
-                                }
-                            }
-                            "static_dir" => {
-                                config.static_dir = value.to_string();
-                            }
-                            "content_dir" => {
-                                config.content_dir = PathBuf::from(value);
-                            }
-                            "base_url" => {
-                                config.base_url = value.to_string();
-                            }
-                            _ => {}
+                    "port" => {
+                        if let Ok(port) = value.parse() {
+                            self.port = port;
                         }
                     }
+                    "static_dir" => {
+                        self.static_dir = value.to_string();
+                    }
+                    "content_dir" => {
+                        self.content_dir = PathBuf::from(value);
+                    }
+                    "base_url" => {
+                        self.base_url = value.to_string();
+                    }
+                    "mode" => {
+                        if let Some(mode) = Mode::parse(value) {
+                            self.mode = mode;
+                        }
+                    }
+                    "author" => {
+                        self.author = value.to_string();
+                    }
+                    "drafts" => {
+                        self.drafts = value.eq_ignore_ascii_case("true");
+                    }
+                    "theme" => {
+                        self.theme = Some(value.to_string()).filter(|theme| !theme.is_empty());
+                    }
+                    "allowed_html" => {
+                        self.allowed_html = value.to_string();
+                    }
+                    _ => {}
                 }
-                break; // Stop after first found config file
             }
         }
-        // Fallback to env if not set by file
-        if let Ok(host) = std::env::var("HOST") {
-            if let Some(parsed) = parse_host(&host) {
-                config.host = parsed;
-            }
+        Ok(())
+    }
+
+    /// Apply settings from environment variables
+    fn apply_env(&mut self) {
+        if let Ok(host) = std::env::var("HOST")
+            && let Some(parsed) = parse_host(&host)
+        {
+            self.host = parsed;
         }
-        if let Ok(port) = std::env::var("PORT") {
-            if let Ok(port) = port.parse() {
-                config.port = port;
-            }
+        if let Ok(port) = std::env::var("PORT")
+            && let Ok(port) = port.parse()
+        {
+            self.port = port;
         }
         if let Ok(static_dir) = std::env::var("STATIC_DIR") {
-            config.static_dir = static_dir;
+            self.static_dir = static_dir;
         }
         if let Ok(content_dir) = std::env::var("CONTENT_DIR") {
-            config.content_dir = PathBuf::from(content_dir);
+            self.content_dir = PathBuf::from(content_dir);
         }
         if let Ok(base_url) = std::env::var("BASE_URL") {
-            config.base_url = base_url;
+            self.base_url = base_url;
+        }
+        if let Ok(mode) = std::env::var("NORNITY_MODE")
+            && let Some(mode) = Mode::parse(&mode)
+        {
+            self.mode = mode;
         }
-        config
+        if let Ok(theme) = std::env::var("NORNITY_THEME") {
+            self.theme = Some(theme).filter(|theme| !theme.is_empty());
+        }
+    }
+
+    /// Get the path of the default config file, if one exists
+    pub fn config_file_path() -> Option<PathBuf> {
+        CONFIG_FILES
+            .iter()
+            .map(PathBuf::from)
+            .find(|path| path.is_file())
     }
 
     /// Get the socket address for the server
@@ -107,6 +236,11 @@ impl Config {
         SocketAddr::from((self.host, self.port))
     }
 
+    /// Check if the server runs in development mode
+    pub fn is_development(&self) -> bool {
+        self.mode == Mode::Development
+    }
+
     /// Check if content directory exists
     pub fn content_dir_exists(&self) -> bool {
         self.content_dir.exists()
@@ -114,7 +248,7 @@ impl Config {
 }
 
 /// Parse host string (e.g. "127.0.0.1" or "0.0.0.0") to [u8; 4]
-fn parse_host(s: &str) -> Option<[u8; 4]> {
+pub fn parse_host(s: &str) -> Option<[u8; 4]> {
     let parts: Vec<_> = s.split('.').collect();
     if parts.len() != 4 {
         return None;
@@ -0,0 +1,344 @@
+//! Stylesheet pipeline: `@import` bundling and minification
+//!
+//! Every stylesheet under the static directory is served as a bundle: local
+//! `@import "other.css";` rules are replaced by the imported file, so a page loads one
+//! file per `<link>`. In production the bundle is also minified. Imports of remote URLs
+//! or with media conditions are kept as `@import` rules at the top of the bundle.
+//! Relative `url(...)` references in imported files are rewritten to `/static/...`
+//! paths, since the bundle is served from the importing file's directory.
+
+use crate::assets::STATIC_PREFIX;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Bundle the stylesheet at `logical` (a path under `static_dir`, e.g. `css/style.css`)
+pub fn bundle(
+    static_dir: &Path,
+    logical: &str,
+    minified: bool,
+) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut bundler = Bundler {
+        static_dir,
+        stack: Vec::new(),
+        included: HashSet::new(),
+        kept_imports: Vec::new(),
+    };
+    let body = bundler.include(logical)?;
+
+    let mut css = bundler.kept_imports.join("\n");
+    if !css.is_empty() {
+        css.push('\n');
+    }
+    css.push_str(&body);
+    Ok(if minified { minify(&css) } else { css })
+}
+
+struct Bundler<'a> {
+    static_dir: &'a Path,
+    /// Files being included, innermost last, to report import cycles
+    stack: Vec<String>,
+    /// Files already in the bundle; a second import of the same file is dropped
+    included: HashSet<String>,
+    /// `@import` rules that stay in the output
+    kept_imports: Vec<String>,
+}
+
+impl Bundler<'_> {
+    fn include(
+        &mut self,
+        logical: &str,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        if self.stack.iter().any(|path| path == logical) {
+            return Err(
+                format!("CSS import cycle: {} -> {logical}", self.stack.join(" -> ")).into(),
+            );
+        }
+        if !self.included.insert(logical.to_string()) {
+            return Ok(String::new());
+        }
+
+        let path = self.static_dir.join(logical);
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read stylesheet {path:?}: {e}"))?;
+        // The outermost file is served from its own directory, so only imports are rebased
+        let imported = !self.stack.is_empty();
+        let own_css = |css: &str| {
+            if imported {
+                rebase_urls(css, logical)
+            } else {
+                css.to_string()
+            }
+        };
+        self.stack.push(logical.to_string());
+
+        let mut out = String::with_capacity(source.len());
+        let mut rest = source.as_str();
+        while let Some(start) = find_import(rest) {
+            out.push_str(&own_css(&rest[..start]));
+            let statement = &rest[start..];
+            let end = statement.find(';').map_or(statement.len(), |end| end + 1);
+            let rule = &statement[..end];
+            rest = &statement[end..];
+
+            match parse_import(rule) {
+                Some((target, "")) if !is_remote(target) => {
+                    let imported = resolve(logical, target).ok_or_else(|| {
+                        format!("Import of {target:?} in {logical} leaves the static directory")
+                    })?;
+                    out.push_str(&self.include(&imported)?);
+                }
+                _ => self.kept_imports.push(rule.trim().to_string()),
+            }
+        }
+        out.push_str(&own_css(rest));
+
+        self.stack.pop();
+        Ok(out)
+    }
+}
+
+/// Rewrite relative `url(...)` references in `css` from the file at `from` to
+/// `/static/...` paths
+///
+/// Remote, `data:`, rooted and fragment-only URLs are left alone, as are paths that
+/// would leave the static directory.
+fn rebase_urls(css: &str, from: &str) -> String {
+    let bytes = css.as_bytes();
+    let mut out = String::with_capacity(css.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = css[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+            }
+            quote @ (b'"' | b'\'') => i = skip_string(bytes, i, quote),
+            b'u' if css[i..].starts_with("url(") => {
+                let Some(end) = css[i..].find(')').map(|end| i + end) else {
+                    break;
+                };
+                let inner = css[i + 4..end].trim();
+                let quote = inner.chars().next().filter(|c| matches!(c, '"' | '\''));
+                let target = inner.trim_matches(['"', '\'']);
+                let relative =
+                    !target.is_empty() && !is_remote(target) && !target.starts_with(['/', '#']);
+                if relative && let Some(path) = resolve(from, target) {
+                    let quote = quote.map(String::from).unwrap_or_default();
+                    out.push_str(&css[copied..i]);
+                    out.push_str(&format!("url({quote}{STATIC_PREFIX}/{path}{quote})"));
+                    copied = end + 1;
+                }
+                i = end + 1;
+            }
+            _ => i += 1,
+        }
+    }
+    out.push_str(&css[copied..]);
+    out
+}
+
+/// Byte offset of the next `@import` rule, skipping comments and strings
+fn find_import(css: &str) -> Option<usize> {
+    let bytes = css.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = css[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + end + 4);
+            }
+            quote @ (b'"' | b'\'') => i = skip_string(bytes, i, quote),
+            b'@' if css[i..].starts_with("@import") => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Split `@import url("a.css") screen;` into its target and any conditions
+fn parse_import(rule: &str) -> Option<(&str, &str)> {
+    let rest = rule
+        .strip_prefix("@import")?
+        .trim()
+        .trim_end_matches(';')
+        .trim_end();
+    let (target, conditions) = if let Some(inner) = rest.strip_prefix("url(") {
+        let end = inner.find(')')?;
+        (inner[..end].trim(), &inner[end + 1..])
+    } else {
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        let end = rest[1..].find(quote)? + 2;
+        (&rest[..end], &rest[end..])
+    };
+    let target = target.trim_matches(['"', '\'']);
+    Some((target, conditions.trim()))
+}
+
+fn is_remote(target: &str) -> bool {
+    target.contains("://") || target.starts_with("//") || target.starts_with("data:")
+}
+
+/// Resolve an import target against the importing file's logical path
+///
+/// `/static/...` targets are taken from the static root. Returns `None` for paths
+/// outside the static directory.
+fn resolve(from: &str, target: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    let target = match target.strip_prefix(STATIC_PREFIX) {
+        Some(rooted) if rooted.starts_with('/') => rooted,
+        _ if target.starts_with('/') => return None,
+        _ => {
+            parts.extend(from.split('/'));
+            parts.pop();
+            target
+        }
+    };
+    for part in target.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Index just past the string starting at `start`
+fn skip_string(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Strip comments and redundant whitespace and semicolons
+///
+/// Strings and `url(...)` arguments are copied untouched. Whitespace is only dropped
+/// where it can never matter: a space before `(` is kept, since `and (` differs from
+/// `and(`, and so is a space before a `:` in a selector, since `a :hover` differs
+/// from `a:hover`.
+pub fn minify(css: &str) -> String {
+    const NO_SPACE_BEFORE: &[char] = &['{', '}', ';', ',', '>', ')'];
+    const NO_SPACE_AFTER: &[char] = &['{', '}', ';', ',', '>', ':', '('];
+
+    let bytes = css.as_bytes();
+    let mut out = String::with_capacity(css.len());
+    let mut space = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = css[i..].chars().next().unwrap_or_default();
+        if c == '/' && bytes.get(i + 1) == Some(&b'*') {
+            i = css[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + end + 4);
+            space = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            space = true;
+            i += c.len_utf8();
+            continue;
+        }
+
+        let space_matters = match c {
+            ':' => in_selector(&css[i..]),
+            _ => !NO_SPACE_BEFORE.contains(&c),
+        };
+        if space && space_matters && !out.is_empty() && !out.ends_with(NO_SPACE_AFTER) {
+            out.push(' ');
+        }
+        space = false;
+
+        let end = match c {
+            '"' | '\'' => skip_string(bytes, i, c as u8),
+            'u' if css[i..].starts_with("url(")
+                && !out.ends_with(|c: char| c.is_alphanumeric() || c == '-') =>
+            {
+                let inner = css[i + 4..].trim_start();
+                if inner.starts_with(['"', '\'']) {
+                    // Quoted URLs are handled as strings
+                    i + 4
+                } else {
+                    css[i..].find(')').map_or(bytes.len(), |end| i + end + 1)
+                }
+            }
+            '}' => {
+                if out.ends_with(';') {
+                    out.pop();
+                }
+                i + 1
+            }
+            _ => i + c.len_utf8(),
+        };
+        out.push_str(&css[i..end]);
+        i = end;
+    }
+    out
+}
+
+/// Whether the text ahead is still part of a selector, which ends at `{`, rather than a
+/// declaration, which ends at `;` or `}`
+fn in_selector(ahead: &str) -> bool {
+    ahead
+        .find(['{', ';', '}'])
+        .is_some_and(|end| ahead.as_bytes()[end] == b'{')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_and_minify() {
+        let dir = std::env::temp_dir().join(format!("nornity-css-{}", std::process::id()));
+        fs::create_dir_all(dir.join("css/parts")).unwrap();
+        fs::write(
+            dir.join("css/style.css"),
+            "@import url(\"https://fonts.example/inter.css\");\n\
+             @import \"parts/base.css\";\n\
+             @import 'parts/base.css';\n\
+             /* layout */\n\
+             .nav a :hover ,\n.nav > li {\n  margin : 0 auto ;\n  width: calc(100% - 2rem);\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("css/parts/base.css"),
+            "body { content: \"a ; b\"; background: url( img/x.png ); }\n\
+             @font-face { src: url(\"../fonts/a.woff2\"), url(data:font/woff2;base64,AA); }\n\
+             @media screen and (min-width: 600px) { p { color: red; } }\n",
+        )
+        .unwrap();
+
+        let css = bundle(&dir, "css/style.css", true).unwrap();
+        assert_eq!(
+            css,
+            "@import url(\"https://fonts.example/inter.css\");\
+             body{content:\"a ; b\";background:url(/static/css/parts/img/x.png)}\
+             @font-face{src:url(\"/static/css/fonts/a.woff2\"),url(data:font/woff2;base64,AA)}\
+             @media screen and (min-width:600px){p{color:red}}\
+             .nav a :hover,.nav>li{margin:0 auto;width:calc(100% - 2rem)}"
+        );
+
+        fs::write(dir.join("css/parts/base.css"), "@import \"../style.css\";").unwrap();
+        let err = bundle(&dir, "css/style.css", false).unwrap_err();
+        assert!(err.to_string().contains("import cycle"), "{err}");
+        assert_eq!(resolve("css/style.css", "../../x.css"), None);
+        assert_eq!(
+            resolve("css/style.css", "/static/fonts.css").as_deref(),
+            Some("fonts.css")
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
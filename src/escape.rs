@@ -0,0 +1,31 @@
+//! HTML escaping for user content
+//!
+//! Everything that comes from posts or requests goes through one of these before it is
+//! written into a page.
+
+/// Escape text placed between tags, e.g. `<p>{text}</p>`
+pub fn escape_text(input: &str) -> String {
+    escape(input, false)
+}
+
+/// Escape a value placed inside a quoted attribute, e.g. `alt="{value}"`
+///
+/// Also safe in element content, so it is the one to use when the context is unknown.
+pub fn escape_attr(input: &str) -> String {
+    escape(input, true)
+}
+
+fn escape(input: &str, quotes: bool) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if quotes => escaped.push_str("&quot;"),
+            '\'' if quotes => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,70 +1,60 @@
-use crate::blog::BlogStore;
-use crate::components::code_block::process_markdown_content;
+use crate::blog::{BlogPost, BlogStore};
+use crate::components::og_image::{self, Card, CardTheme};
 use crate::components::rss::generate_rss_feed_xml;
-use crate::components::search::{SearchComponent, SearchResult, SearchResults, SortOption};
+use crate::components::search::SearchComponent;
 use crate::components::sitemap::generate_sitemap_xml;
-use crate::template_helpers::{render_blog_preview, render_tags};
-use crate::templates::TemplateEngine;
+use crate::escape::escape_text;
+use crate::page_meta::PageMeta;
+use crate::templates::{Context, TemplateEngine, Value};
 use axum::{
+    body::Body,
     extract::{Path, Query},
-    http::{StatusCode, header},
+    http::{HeaderMap, StatusCode, header},
     response::{Html, Response},
 };
 use log::{debug, error, info, warn};
+use std::borrow::Borrow;
 use std::collections::HashMap;
 
+/// Result of rendering a page or feed
+pub type RenderResult = Result<String, Box<dyn std::error::Error + Send + Sync>>;
+
 /// Homepage handler
 pub async fn homepage() -> Html<String> {
     info!("Serving homepage request");
     debug!("Homepage route accessed");
 
+    match render_homepage() {
+        Ok(html) => {
+            info!("Homepage served successfully");
+            Html(html)
+        }
+        Err(e) => {
+            error!("Failed to render homepage: {e}");
+            error_page("Error", &e)
+        }
+    }
+}
+
+/// Render the homepage
+pub fn render_homepage() -> RenderResult {
     let blog_store = get_blog_store();
     let recent_posts = blog_store.get_recent_posts(3);
     debug!("Retrieved {} recent posts for homepage", recent_posts.len());
 
-    let posts_html = recent_posts
-        .iter()
-        .map(|post| {
-            debug!("Processing post for homepage: {}", post.slug);
-            render_blog_preview(
-                &post.slug,
-                post.title(),
-                &post.formatted_date(),
-                &post.iso_date(),
-                post.author(),
-                post.excerpt(),
-                post.tags(),
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    debug!("Generated homepage HTML with {} posts", recent_posts.len());
-    info!("Homepage served successfully");
-
     let template_engine = get_template_engine();
-    let mut variables = HashMap::new();
-    variables.insert("recent_posts".to_string(), posts_html);
-
-    match template_engine.render("homepage.html", &variables) {
-        Ok(content) => {
-            match template_engine.render_base_with_meta(
-                "Nornity - OS Designer & Developer",
-                &content,
-                "OS Designer & Developer specializing in systems programming, compiler design, and low-level development. Explore my projects, blog posts, and technical insights."
-            ) {
-                Ok(html) => Html(html),
-                Err(e) => {
-                    error!("Failed to render base template: {e}");
-                    Html(format!("<h1>Error</h1><p>Failed to render page: {e}</p>"))
-                }
-            }
-        }
-        Err(e) => {
-            error!("Failed to render homepage template: {e}");
-            Html(format!("<h1>Error</h1><p>Failed to render page: {e}</p>"))
-        }
-    }
+    let page = PageMeta::new(
+        &get_config().base_url,
+        "/",
+        "OS Designer & Developer",
+        "OS Designer & Developer specializing in systems programming, compiler design, and \
+         low-level development. Explore my projects, blog posts, and technical insights.",
+    );
+    let mut context = Context::new();
+    context.insert("page", &page);
+    context.insert("posts", post_values(&recent_posts));
+
+    template_engine.render("homepage.html", &context)
 }
 
 /// Blog list handler with server-side search and tag filtering
@@ -79,37 +69,39 @@ pub async fn blog_list(
     let query_string = req.uri().query().unwrap_or("");
     debug!("Raw query string: {}", query_string);
 
+    match render_blog_list(query_string) {
+        Ok(html) => {
+            info!("Blog list page served successfully");
+            Html(html)
+        }
+        Err(e) => {
+            error!("Failed to render blog list: {e}");
+            error_page("Error", &e)
+        }
+    }
+}
+
+/// Render the blog list for a raw query string (search, tags and sort order)
+pub fn render_blog_list(query_string: &str) -> RenderResult {
     // Parse search configuration from raw query string to handle multiple tags
     let search_config = SearchComponent::parse_params_from_raw_query(query_string);
     debug!("Search config: {:?}", search_config);
 
-    let blog_store = get_blog_store();
-    let total_posts = blog_store.get_all_posts();
-    let total_count = total_posts.len();
+    let snapshot = get_blog_store().snapshot();
+    let total_count = snapshot.len();
 
-    // Apply filters
-    let mut posts = if !search_config.selected_tags.is_empty() {
+    // For multiple tags, keep only posts that have ALL selected tags
+    let mut posts = snapshot.list(&search_config.sort_by, &search_config.selected_tags);
+    if !search_config.selected_tags.is_empty() {
         info!("Filtering posts by tags: {:?}", search_config.selected_tags);
-
-        // For multiple tags, we need to find posts that have ALL selected tags
-        let mut filtered_posts = total_posts;
-        for tag in &search_config.selected_tags {
-            let tag_posts = blog_store.get_posts_by_tag(tag);
-            // Keep only posts that exist in both current filtered and tag posts
-            filtered_posts
-                .retain(|post| tag_posts.iter().any(|tag_post| tag_post.slug == post.slug));
-        }
-
         debug!(
             "Found {} posts with all tags {:?}",
-            filtered_posts.len(),
+            posts.len(),
             search_config.selected_tags
         );
-        filtered_posts
     } else {
         debug!("No tag filter applied, getting all posts");
-        total_posts
-    };
+    }
 
     // Apply search filter if provided
     if let Some(ref search_term) = search_config.search_term {
@@ -126,98 +118,44 @@ pub async fn blog_list(
         debug!("Found {} posts matching search term", posts.len());
     }
 
-    // Apply sorting
-    posts.sort_by(|a, b| match search_config.sort_by {
-        SortOption::DateNewest => b.meta.published_at.cmp(&a.meta.published_at),
-        SortOption::DateOldest => a.meta.published_at.cmp(&b.meta.published_at),
-        SortOption::Title => a.title().cmp(b.title()),
-        SortOption::Relevance => {
-            // For relevance, we'll keep the current order (search results first)
-            std::cmp::Ordering::Equal
-        }
-    });
-
-    let filtered_count = posts.len();
-
-    // Convert to search results
-    let search_results = SearchResults {
-        posts: posts
-            .iter()
-            .map(|post| SearchResult {
-                slug: post.slug.clone(),
-                title: post.title().to_string(),
-                excerpt: post.excerpt().to_string(),
-                date: post.formatted_date(),
-                iso_date: post.iso_date(),
-                author: post.author().to_string(),
-                tags: post.tags().to_vec(),
-                relevance_score: 1.0, // Simple relevance for now
-            })
-            .collect(),
-        total_count,
-        filtered_count,
-        search_term: search_config.search_term.clone(),
-        selected_tags: search_config.selected_tags.clone(),
-    };
-
-    // Generate HTML using search component
-    let posts_html = search_results
-        .posts
-        .iter()
-        .map(|result| {
-            debug!("Processing search result: {}", result.slug);
-            render_blog_preview(
-                &result.slug,
-                &result.title,
-                &result.date,
-                &result.iso_date,
-                &result.author,
-                &result.excerpt,
-                &result.tags,
-            )
+    debug!("Rendering blog list with {} posts", posts.len());
+
+    // Tag cloud entries (tags are already sorted in the snapshot)
+    let selected_tags = &search_config.selected_tags;
+    let tags: Vec<Value> = snapshot
+        .tags()
+        .map(|tag| {
+            [
+                ("name", Value::from(tag)),
+                ("active", selected_tags.iter().any(|t| t == tag).into()),
+                (
+                    "url",
+                    SearchComponent::toggle_tag_url(selected_tags, tag).into(),
+                ),
+            ]
+            .into_iter()
+            .collect()
         })
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    // Get all tags for the tag cloud
-    let mut all_tags = blog_store.get_all_tags();
-    all_tags.sort();
-
-    // Generate search form and tag cloud using the search component
-    let search_form = SearchComponent::render_search_form(&search_config);
-    let tag_cloud = SearchComponent::render_tag_cloud(&all_tags, &search_config.selected_tags);
-    let search_summary = SearchComponent::render_search_summary(&search_results);
-
-    debug!(
-        "Generated blog list HTML with {} posts",
-        search_results.filtered_count
+        .collect();
+
+    let page = PageMeta::new(
+        &get_config().base_url,
+        "/blog",
+        "Blog",
+        "Read my latest thoughts on systems programming, compiler design, OS development, and \
+         technical insights. Browse articles by tags or search for specific topics.",
     );
-    info!("Blog list page served successfully");
 
     let template_engine = get_template_engine();
-    let mut variables = HashMap::new();
-    variables.insert("search_form".to_string(), search_form);
-    variables.insert("tag_cloud".to_string(), tag_cloud);
-    variables.insert("search_summary".to_string(), search_summary);
-    variables.insert("blog_posts".to_string(), posts_html);
-
-    match template_engine.render("blog_list.html", &variables) {
-        Ok(content) => match template_engine.render_base_with_meta(
-            "Blog",
-            &content,
-            "Read my latest thoughts on systems programming, compiler design, OS development, and technical insights. Browse articles by tags or search for specific topics."
-        ) {
-            Ok(html) => Html(html),
-            Err(e) => {
-                error!("Failed to render base template: {e}");
-                Html(format!("<h1>Error</h1><p>Failed to render page: {e}</p>"))
-            }
-        },
-        Err(e) => {
-            error!("Failed to render blog list template: {e}");
-            Html(format!("<h1>Error</h1><p>Failed to render page: {e}</p>"))
-        }
-    }
+    let mut context = Context::new();
+    context.insert("page", &page);
+    context.insert("posts", post_values(&posts));
+    context.insert("total_count", total_count);
+    context.insert("search", search_config.search_term.clone());
+    context.insert("selected_tags", selected_tags.clone());
+    context.insert("tags", tags);
+
+    template_engine.render("blog_list.html", &context)
 }
 
 /// Individual blog post handler
@@ -237,64 +175,98 @@ pub async fn blog_post(Path(slug): Path<String>) -> Result<Html<String>, StatusC
         }
     };
 
-    // Process markdown content with custom components and enhanced code blocks
-    debug!("Rendering markdown content for post: {slug}");
-    let preprocessed =
-        crate::components::custom_components::preprocess_markdown_with_components(&post.content);
-    let html_content = process_markdown_content(&preprocessed);
-    debug!("Enhanced HTML content length: {} chars", html_content.len());
-    debug!(
-        "Markdown rendered successfully, content length: {} chars",
-        html_content.len()
-    );
+    match render_blog_post(&post) {
+        Ok(html) => Ok(Html(html)),
+        Err(e) => {
+            error!("Failed to render blog post {slug}: {e}");
+            Ok(error_page("Error", &e))
+        }
+    }
+}
+
+/// Render a single blog post page
+pub fn render_blog_post(post: &BlogPost) -> RenderResult {
+    // Markdown is rendered once when the post is loaded
+    debug!("Using pre-rendered content for post: {}", post.slug);
+    debug!("Rendered content length: {} chars", post.html.len());
 
     let template_engine = get_template_engine();
-    let mut variables = HashMap::new();
-    variables.insert("post_title".to_string(), post.title().to_string());
-    variables.insert("post_date".to_string(), post.formatted_date());
-    variables.insert("post_iso_date".to_string(), post.iso_date());
-    variables.insert("post_author".to_string(), post.author().to_string());
-    variables.insert("post_content".to_string(), html_content);
-    variables.insert("post_tags".to_string(), render_tags(post.tags()));
-
-    match template_engine.render("blog_post.html", &variables) {
-        Ok(content) => match template_engine.render_base_with_meta_and_css_list(
-            post.title(),
-            &content,
-            post.excerpt(),
-            &[
-                "/static/css/code-blocks.min.css?v=3",
-                "/static/css/blog-post.css?v=1",
-            ],
-        ) {
-            Ok(html) => Ok(Html(html)),
-            Err(e) => {
-                error!("Failed to render base template: {e}");
-                Ok(Html(format!(
-                    "<h1>Error</h1><p>Failed to render page: {e}</p>"
-                )))
-            }
-        },
+    let page = PageMeta::for_post(&get_config().base_url, post);
+    let mut context = Context::new();
+    context.insert("page", &page);
+    context.insert("post", post);
+    context.insert("content", Value::safe(post.html.as_str()));
+
+    template_engine.render("blog_post.html", &context)
+}
+
+/// Share card image handler
+pub async fn og_image(
+    Path(slug): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, StatusCode> {
+    debug!("Share card request for slug: {slug}");
+
+    let Some(post) = get_blog_store().get_post_by_slug(&slug) else {
+        warn!("Share card requested for unknown post: {slug}");
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    // Rasterizing takes a while, so keep it off the async workers
+    let rendered = tokio::task::spawn_blocking(move || render_og_image(&post))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    match rendered {
+        Ok(card) => {
+            let etag = format!("\"{:016x}\"", card.hash);
+            let response = Response::builder()
+                .header(header::ETAG, &etag)
+                .header(header::CACHE_CONTROL, "public, max-age=3600");
+            let response = if etag_matches(&headers, &etag) {
+                debug!("Share card for {slug} not modified");
+                response
+                    .status(StatusCode::NOT_MODIFIED)
+                    .body(Body::empty())
+            } else {
+                response
+                    .status(StatusCode::OK)
+                    .header(header::CONTENT_TYPE, "image/png")
+                    .body(Body::from(card.png.as_ref().clone()))
+            };
+            response.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+        }
         Err(e) => {
-            error!("Failed to render blog post template: {e}");
-            Ok(Html(format!(
-                "<h1>Error</h1><p>Failed to render page: {e}</p>"
-            )))
+            error!("Failed to render share card for {slug}: {e}");
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
+/// Render a post's share card, reusing the cached one if the card is unchanged
+pub fn render_og_image(post: &BlogPost) -> Result<Card, Box<dyn std::error::Error + Send + Sync>> {
+    let assets = crate::routes::get_assets();
+    let logo = og_image::logo(std::path::Path::new(&get_config().static_dir), &assets);
+    let svg = og_image::card_svg(post, logo.as_deref(), &CardTheme::from_assets(&assets));
+    crate::routes::get_card_cache().get_or_render(&post.slug, &svg)
+}
+
+/// Whether the request's `If-None-Match` header lists `etag`, or is `*`
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
 /// Sitemap XML handler
 pub async fn sitemap() -> Result<Response<String>, StatusCode> {
     info!("Serving sitemap.xml request");
     debug!("Sitemap route accessed");
 
-    let blog_store = get_blog_store();
-
-    // Get base URL from config (loaded from file/env/defaults)
-    let base_url = get_config().base_url.clone();
-
-    match generate_sitemap_xml(&base_url, blog_store) {
+    match render_sitemap() {
         Ok(xml) => {
             info!("Sitemap generated successfully");
             debug!("Sitemap XML length: {} chars", xml.len());
@@ -314,21 +286,19 @@ pub async fn sitemap() -> Result<Response<String>, StatusCode> {
     }
 }
 
+/// Render sitemap.xml
+pub fn render_sitemap() -> RenderResult {
+    // Get base URL from config (loaded from file/env/defaults)
+    let base_url = get_config().base_url.clone();
+    generate_sitemap_xml(&base_url, get_blog_store())
+}
+
 /// Robots.txt handler
 pub async fn robots_txt() -> Result<Response<String>, StatusCode> {
     info!("Serving robots.txt request");
     debug!("Robots.txt route accessed");
 
-    // Get base URL from config (loaded from file/env/defaults)
-    let base_url = get_config().base_url.clone();
-
-    let robots_content = format!(
-        "User-agent: *\n\
-         Allow: /\n\
-         \n\
-         Sitemap: {}/sitemap.xml\n",
-        base_url
-    );
+    let robots_content = render_robots_txt();
 
     let response = Response::builder()
         .status(StatusCode::OK)
@@ -339,23 +309,26 @@ pub async fn robots_txt() -> Result<Response<String>, StatusCode> {
     Ok(response)
 }
 
+/// Render robots.txt
+pub fn render_robots_txt() -> String {
+    // Get base URL from config (loaded from file/env/defaults)
+    let base_url = get_config().base_url.clone();
+
+    format!(
+        "User-agent: *\n\
+         Allow: /\n\
+         \n\
+         Sitemap: {}/sitemap.xml\n",
+        base_url
+    )
+}
+
 /// RSS feed handler
 pub async fn rss_feed() -> Result<Response<String>, StatusCode> {
     info!("Serving RSS feed request");
     debug!("RSS feed route accessed");
 
-    let blog_store = get_blog_store();
-
-    // Get base URL from config (loaded from file/env/defaults)
-    let base_url = get_config().base_url.clone();
-
-    match generate_rss_feed_xml(
-        &base_url,
-        "Nornity - OS Designer & Developer",
-        "OS Designer & Developer specializing in systems programming, compiler design, and low-level development.",
-        "Nornity",
-        blog_store,
-    ) {
+    match render_rss_feed() {
         Ok(xml) => {
             info!("RSS feed generated successfully");
             debug!("RSS feed XML length: {} chars", xml.len());
@@ -375,61 +348,72 @@ pub async fn rss_feed() -> Result<Response<String>, StatusCode> {
     }
 }
 
+/// Render rss.xml
+pub fn render_rss_feed() -> RenderResult {
+    // Get base URL from config (loaded from file/env/defaults)
+    let base_url = get_config().base_url.clone();
+
+    generate_rss_feed_xml(
+        &base_url,
+        "Nornity - OS Designer & Developer",
+        "OS Designer & Developer specializing in systems programming, compiler design, and low-level development.",
+        "Nornity",
+        get_blog_store(),
+    )
+}
+
 /// 404 Not Found handler
 pub async fn not_found() -> Html<String> {
     info!("Serving 404 page");
     debug!("404 route accessed");
 
+    match render_not_found() {
+        Ok(html) => Html(html),
+        Err(e) => {
+            error!("Failed to render 404 page: {e}");
+            error_page("404 - Page Not Found", &e)
+        }
+    }
+}
+
+/// Render the 404 page
+pub fn render_not_found() -> RenderResult {
     let blog_store = get_blog_store();
     let recent_posts = blog_store.get_recent_posts(3);
     debug!("Retrieved {} recent posts for 404 page", recent_posts.len());
 
-    // Generate recent posts suggestions for the 404 page
-    let posts_html = recent_posts
+    let template_engine = get_template_engine();
+    let page = PageMeta::new(
+        &get_config().base_url,
+        "/404",
+        "Page Not Found",
+        "The page you're looking for doesn't exist. Explore our blog posts or return to the \
+         homepage.",
+    )
+    // Served at whatever URL was missed, so it has no canonical URL of its own
+    .without_url();
+    let mut context = Context::new();
+    context.insert("page", &page);
+    context.insert("posts", post_values(&recent_posts));
+
+    template_engine.render("404.html", &context)
+}
+
+/// Template values for a list of posts
+fn post_values<P: Borrow<BlogPost>>(posts: &[P]) -> Vec<Value> {
+    posts
         .iter()
-        .map(|post| {
-            debug!("Processing post for 404 page: {}", post.slug);
-            format!(
-                r#"<div class="suggested-post">
-                    <a href="/blog/{}" class="suggested-post-link">
-                        <h4 class="suggested-post-title">{}</h4>
-                        <p class="suggested-post-excerpt">{}</p>
-                        <span class="suggested-post-date">{}</span>
-                    </a>
-                </div>"#,
-                post.slug,
-                post.title(),
-                post.excerpt(),
-                post.formatted_date()
-            )
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
+        .map(|post| Value::from(post.borrow()))
+        .collect()
+}
 
-    let template_engine = get_template_engine();
-    let mut variables = HashMap::new();
-    variables.insert("recent_posts_suggestion".to_string(), posts_html);
-
-    match template_engine.render("404.html", &variables) {
-        Ok(content) => {
-            match template_engine.render_base_with_meta_and_css_list(
-                "Page Not Found - Nornity",
-                &content,
-                "The page you're looking for doesn't exist. Explore our blog posts or return to the homepage.",
-                &["/static/css/404.css"]
-            ) {
-                Ok(html) => Html(html),
-                Err(e) => {
-                    error!("Failed to render 404 base template: {e}");
-                    Html(format!("<h1>404 - Page Not Found</h1><p>Failed to render page: {e}</p>"))
-                }
-            }
-        }
-        Err(e) => {
-            error!("Failed to render 404 template: {e}");
-            Html(format!("<h1>404 - Page Not Found</h1><p>Failed to render page: {e}</p>"))
-        }
-    }
+/// Fallback page shown when rendering fails
+fn error_page(heading: &str, error: &dyn std::fmt::Display) -> Html<String> {
+    Html(format!(
+        "<h1>{}</h1><p>Failed to render page: {}</p>",
+        escape_text(heading),
+        escape_text(&error.to_string())
+    ))
 }
 
 // Helper functions to access global instances
@@ -437,10 +421,10 @@ fn get_blog_store() -> &'static BlogStore {
     crate::routes::get_blog_store()
 }
 
-fn get_template_engine() -> &'static TemplateEngine {
+fn get_template_engine() -> std::sync::Arc<TemplateEngine> {
     crate::routes::get_template_engine()
 }
 
-fn get_config() -> &'static crate::config::Config {
+fn get_config() -> std::sync::Arc<crate::config::Config> {
     crate::routes::get_config()
 }
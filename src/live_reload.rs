@@ -0,0 +1,184 @@
+//! Browser live reload over server-sent events
+//!
+//! Only active with the `hot-reload` feature in development mode. Pages get a small
+//! script that listens on [`LIVE_RELOAD_PATH`] and reloads when the watcher reports a
+//! change, or just refreshes stylesheets when only CSS changed.
+
+use crate::config::Config;
+use axum::Router;
+use log::info;
+use std::sync::OnceLock;
+
+#[cfg(feature = "hot-reload")]
+use axum::response::sse::{Event, KeepAlive, Sse};
+#[cfg(feature = "hot-reload")]
+use std::convert::Infallible;
+#[cfg(feature = "hot-reload")]
+use tokio::sync::broadcast;
+#[cfg(feature = "hot-reload")]
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+/// Endpoint the injected script subscribes to
+pub const LIVE_RELOAD_PATH: &str = "/__nornity/live";
+
+/// Script injected into every page while live reload is enabled
+const LIVE_RELOAD_SCRIPT: &str = r#"<script>
+(() => {
+    const source = new EventSource("/__nornity/live");
+    let lost = false;
+    source.onerror = () => { lost = true; };
+    // Reconnecting after the server restarted: the page may be stale
+    source.onopen = () => { if (lost) location.reload(); };
+    source.addEventListener("reload", () => location.reload());
+    source.addEventListener("css", () => {
+        for (const link of document.querySelectorAll('link[rel="stylesheet"]')) {
+            const url = new URL(link.href);
+            url.searchParams.set("live", Date.now());
+            link.href = url.href;
+        }
+    });
+})();
+</script>"#;
+
+/// Whether live reload was enabled at startup
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// What the browser should do after a change
+#[cfg(feature = "hot-reload")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveEvent {
+    /// Reload the whole page
+    Reload,
+    /// Only stylesheets changed; refresh them in place
+    Css,
+}
+
+#[cfg(feature = "hot-reload")]
+impl LiveEvent {
+    fn name(self) -> &'static str {
+        match self {
+            LiveEvent::Reload => "reload",
+            LiveEvent::Css => "css",
+        }
+    }
+}
+
+/// Decide once whether live reload is on and add its endpoint to the router
+pub fn mount(router: Router, config: &Config) -> Router {
+    let enabled = *ENABLED.get_or_init(|| enabled_for(config));
+    add_endpoint(router, enabled)
+}
+
+/// Whether live reload can run with `config`: it is never enabled in production mode
+fn enabled_for(config: &Config) -> bool {
+    cfg!(feature = "hot-reload") && config.is_development()
+}
+
+fn add_endpoint(router: Router, enabled: bool) -> Router {
+    if !enabled {
+        return router;
+    }
+
+    info!("Live reload enabled at {LIVE_RELOAD_PATH}");
+    #[cfg(feature = "hot-reload")]
+    let router = router.route(LIVE_RELOAD_PATH, axum::routing::get(live_events));
+    router
+}
+
+/// Check if live reload is enabled
+pub fn is_enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// Get the script tag to inject into pages, empty unless live reload is enabled
+pub fn script() -> &'static str {
+    script_for(is_enabled())
+}
+
+fn script_for(enabled: bool) -> &'static str {
+    if enabled { LIVE_RELOAD_SCRIPT } else { "" }
+}
+
+#[cfg(feature = "hot-reload")]
+fn sender() -> &'static broadcast::Sender<LiveEvent> {
+    static SENDER: OnceLock<broadcast::Sender<LiveEvent>> = OnceLock::new();
+    SENDER.get_or_init(|| broadcast::channel(16).0)
+}
+
+/// Tell connected browsers about a change
+#[cfg(feature = "hot-reload")]
+pub fn notify(event: LiveEvent) {
+    if !is_enabled() {
+        return;
+    }
+    // Sending only fails when no browser is listening
+    if let Ok(receivers) = sender().send(event) {
+        log::debug!(
+            "Live reload: sent {} to {receivers} browser(s)",
+            event.name()
+        );
+    }
+}
+
+/// Server-sent events handler for the live reload endpoint
+#[cfg(feature = "hot-reload")]
+pub async fn live_events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    log::debug!("Live reload client connected");
+    let stream = BroadcastStream::new(sender().subscribe()).map(|event| {
+        // A client that fell behind missed something; a full reload catches it up
+        let event = event.unwrap_or(LiveEvent::Reload);
+        Ok(Event::default().event(event.name()).data(""))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Mode;
+    use std::io::{Read, Write};
+
+    /// Status line of a GET request for `path` to a server running `router`
+    async fn status(router: Router, path: &str) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        tokio::task::spawn_blocking(move || {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            // The event stream never ends, so read only what arrives first
+            let mut buf = [0; 512];
+            let read = stream.read(&mut buf).unwrap();
+            let response = String::from_utf8_lossy(&buf[..read]).into_owned();
+            response.lines().next().unwrap_or_default().to_string()
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_live_reload_only_in_development() {
+        let mut config = Config::default();
+        assert!(!enabled_for(&config));
+        config.mode = Mode::Development;
+        assert_eq!(enabled_for(&config), cfg!(feature = "hot-reload"));
+
+        // Nothing is mounted or injected unless enabled
+        assert!(!is_enabled());
+        assert_eq!(script(), "");
+        assert_eq!(
+            status(add_endpoint(Router::new(), false), LIVE_RELOAD_PATH).await,
+            "HTTP/1.1 404 Not Found"
+        );
+
+        if cfg!(feature = "hot-reload") {
+            assert!(script_for(true).contains(LIVE_RELOAD_PATH));
+            assert_eq!(
+                status(add_endpoint(Router::new(), true), LIVE_RELOAD_PATH).await,
+                "HTTP/1.1 200 OK"
+            );
+        }
+    }
+}
@@ -6,6 +6,7 @@ use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 pub enum LogOutput {
+    #[allow(dead_code)]
     Stdout,
     Stderr,
 }
@@ -49,16 +50,14 @@ impl Logger {
         let mut file = None;
 
         // Create log directory if it doesn't exist
-        if let Some(path_ref) = path.as_ref() {
-            if let Some(parent) = path_ref.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
+        if let Some(path_ref) = path.as_ref()
+            && let Some(parent) = path_ref.parent()
+        {
+            let _ = std::fs::create_dir_all(parent);
         }
 
-        if write_to_file {
-            if let Some(path_ref) = path.as_ref() {
-                file = File::create(path_ref).ok().map(|f| Arc::new(Mutex::new(f)));
-            }
+        if write_to_file && let Some(path_ref) = path.as_ref() {
+            file = File::create(path_ref).ok().map(|f| Arc::new(Mutex::new(f)));
         }
 
         Logger {
@@ -87,12 +86,14 @@ impl Logger {
     }
 
     /// Initialize logger with environment variables
-    pub fn init() -> Result<(), log::SetLoggerError> {
+    ///
+    /// `NORNITY_LOG` or `RUST_LOG` override the default severity.
+    pub fn init(default_severity: Level) -> Result<(), log::SetLoggerError> {
         let severity = std::env::var("NORNITY_LOG")
             .or_else(|_| std::env::var("RUST_LOG"))
-            .unwrap_or_else(|_| "info".to_string())
-            .parse::<Level>()
-            .unwrap_or(Level::Info);
+            .ok()
+            .and_then(|level| level.parse::<Level>().ok())
+            .unwrap_or(default_severity);
 
         let write_to_std = Some(LogOutput::Stderr);
         let write_to_file = std::env::var("NORNITY_LOG_FILE").is_ok();
@@ -147,13 +148,12 @@ impl Log for Logger {
         }
 
         // Write to file (without colors)
-        if self.write_to_file {
-            if let Some(file) = &self.file {
-                if let Ok(mut file_guard) = file.lock() {
-                    let file_message = format!("[{timestamp}] {level_str} {args}");
-                    let _ = writeln!(file_guard, "{file_message}");
-                }
-            }
+        if self.write_to_file
+            && let Some(file) = &self.file
+            && let Ok(mut file_guard) = file.lock()
+        {
+            let file_message = format!("[{timestamp}] {level_str} {args}");
+            let _ = writeln!(file_guard, "{file_message}");
         }
     }
 
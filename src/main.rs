@@ -1,27 +1,48 @@
 mod app;
+mod assets;
 mod blog;
+mod build;
+mod cli;
 mod components;
 mod config;
+mod css;
+mod escape;
 mod handlers;
+mod hash;
+mod live_reload;
 mod logger;
+mod page_meta;
 mod routes;
+mod sanitize;
 mod server;
-mod template_helpers;
 mod templates;
 mod watcher;
 
-use log::{debug, error, info, warn};
+use cli::Command;
+use log::{Level, debug, error, info};
 use logger::Logger;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            eprintln!("Error: {e}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // Server and build logs are their output; other commands only surface problems
+    let default_severity = match cli.command {
+        Command::Serve | Command::Build(_) => Level::Info,
+        _ => Level::Warn,
+    };
+
     // Initialize logger FIRST, before any other code
-    match Logger::init() {
+    match Logger::init(default_severity) {
         Ok(_) => {
-            info!("Logger initialized successfully");
-            debug!("This is a debug message test");
-            warn!("This is a warning message test");
-            error!("This is an error message test");
+            debug!("Logger initialized successfully");
         }
         Err(e) => {
             eprintln!("Failed to initialize logger: {e}");
@@ -29,12 +50,48 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
-    // Load configuration from file, env, or defaults
-    let config = config::Config::from_file_or_env();
+    // Load configuration from file, env, command-line overrides, or defaults
+    let config = match config::Config::load(cli.overrides) {
+        Ok(config) => config,
+        Err(e) => fail(&e),
+    };
     debug!("Configuration loaded: {config:?}");
 
-    // Initialize global config instance
-    routes::get_config();
+    // Every command renders through the global config, e.g. `check` sanitizes with its allowlist
+    routes::set_config(config.clone());
+
+    match cli.command {
+        Command::Serve => serve(config).await?,
+        Command::New(options) => match cli::new_post(&config, &options).await {
+            Ok(path) => println!("Created {}", path.display()),
+            Err(e) => fail(&e),
+        },
+        Command::List => {
+            if let Err(e) = cli::list_posts(&config).await {
+                fail(&e);
+            }
+        }
+        Command::Build(options) => {
+            if let Err(e) = build::build_site(config, &options).await {
+                error!("Build failed: {e}");
+                fail(&format!("Build failed: {e}"));
+            }
+        }
+        Command::Check => match cli::check(&config).await {
+            Ok(0) => println!("No problems found"),
+            Ok(problems) => fail(&format!("{problems} problem(s) found")),
+            Err(e) => fail(&e),
+        },
+        Command::Help => print!("{}", cli::USAGE),
+        Command::Version => println!("nornity {}", env!("CARGO_PKG_VERSION")),
+    }
+
+    Ok(())
+}
+
+/// Run the web server until it shuts down
+async fn serve(config: config::Config) -> Result<(), Box<dyn std::error::Error>> {
+    info!("Starting Nornity server");
 
     // Initialize application
     app::init_app(&config).await?;
@@ -47,3 +104,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Report a command failure and exit with a non-zero status
+fn fail(message: &dyn std::fmt::Display) -> ! {
+    eprintln!("Error: {message}");
+    std::process::exit(1);
+}
@@ -0,0 +1,243 @@
+//! Page metadata for link previews and search engines
+//!
+//! Every page passes a [`PageMeta`] to its template as `page`; `base.html` turns it into
+//! the canonical link, Open Graph and Twitter tags, and JSON-LD for posts.
+
+use crate::blog::BlogPost;
+use crate::templates::Value;
+use chrono::{DateTime, Utc};
+
+/// Site name shown in link previews
+pub const SITE_NAME: &str = "Nornity";
+
+/// Share image used when a page has none of its own
+const DEFAULT_IMAGE: &str = "/static/assets/favicon/android-chrome-512x512.png";
+
+/// Metadata describing a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageMeta {
+    pub title: String,
+    pub description: String,
+    /// Absolute canonical URL, `None` for pages that must not claim one, like the 404 page
+    pub url: Option<String>,
+    /// Absolute URL of the page's own share image, if it has one
+    pub image: Option<String>,
+    /// Absolute URL of the site root, which the default share image is served from
+    pub site_url: String,
+    /// Set for blog posts, which are shared as articles
+    pub article: Option<Article>,
+}
+
+/// Article details of a blog post page
+#[derive(Debug, Clone, PartialEq)]
+pub struct Article {
+    pub published: DateTime<Utc>,
+    pub author: String,
+    pub tags: Vec<String>,
+}
+
+impl PageMeta {
+    /// Metadata for a regular page at `path` (e.g. `/blog`)
+    pub fn new(
+        base_url: &str,
+        path: &str,
+        title: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            description: description.into(),
+            url: Some(absolute_url(base_url, path)),
+            image: None,
+            site_url: base_url.trim_end_matches('/').to_string(),
+            article: None,
+        }
+    }
+
+    /// Drop the canonical URL, for pages served at many URLs such as the 404 page
+    pub fn without_url(mut self) -> Self {
+        self.url = None;
+        self
+    }
+
+    /// Metadata for a blog post, honouring its `image` and `canonical_url` frontmatter
+    ///
+    /// Without an `image`, the post's generated share card is used.
+    pub fn for_post(base_url: &str, post: &BlogPost) -> Self {
+        let url = match &post.meta.canonical_url {
+            Some(canonical_url) => absolute_url(base_url, canonical_url),
+            None => absolute_url(base_url, &format!("/blog/{}", post.slug)),
+        };
+        let mut tags = post.tags().to_vec();
+        tags.sort();
+
+        Self {
+            title: post.title().to_string(),
+            description: post.excerpt().to_string(),
+            url: Some(url),
+            image: Some(match &post.meta.image {
+                Some(image) => absolute_url(base_url, image),
+                None => absolute_url(base_url, &format!("/blog/{}/og.png", post.slug)),
+            }),
+            site_url: base_url.trim_end_matches('/').to_string(),
+            article: Some(Article {
+                published: post.meta.published_at,
+                author: post.author().to_string(),
+                tags,
+            }),
+        }
+    }
+
+    /// `BlogPosting` structured data for articles
+    fn json_ld(&self, image: &str) -> Option<Value> {
+        let article = self.article.as_ref()?;
+        let author: Value = [("@type", "Person"), ("name", article.author.as_str())]
+            .into_iter()
+            .collect();
+        let publisher: Value = [("@type", "Organization"), ("name", SITE_NAME)]
+            .into_iter()
+            .collect();
+        Some(
+            [
+                ("@context", Value::from("https://schema.org")),
+                ("@type", "BlogPosting".into()),
+                ("headline", self.title.as_str().into()),
+                ("description", self.description.as_str().into()),
+                ("url", self.url.as_deref().into()),
+                ("mainEntityOfPage", self.url.as_deref().into()),
+                ("image", image.into()),
+                ("author", author),
+                ("publisher", publisher),
+                ("datePublished", article.published.into()),
+                // Posts have no separate update date
+                ("dateModified", article.published.into()),
+                ("keywords", article.tags.join(", ").into()),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+}
+
+/// Template value: `title`, `description`, `url`, `image`, `type`, `twitter_card`,
+/// `site_name`, and for posts `article` (`published`, `author`, `tags`) and `json_ld`
+impl From<&PageMeta> for Value {
+    fn from(page: &PageMeta) -> Self {
+        let default_image = absolute_url(&page.site_url, DEFAULT_IMAGE);
+        let image = page.image.as_deref().unwrap_or(&default_image);
+        let article = page.article.as_ref().map(|article| {
+            [
+                ("published", Value::from(article.published)),
+                ("author", article.author.as_str().into()),
+                ("tags", article.tags.clone().into()),
+            ]
+            .into_iter()
+            .collect::<Value>()
+        });
+        // Small default icon vs. a proper banner image
+        let twitter_card = if page.image.is_some() {
+            "summary_large_image"
+        } else {
+            "summary"
+        };
+
+        [
+            ("title", Value::from(page.title.as_str())),
+            ("description", page.description.as_str().into()),
+            ("url", page.url.as_deref().into()),
+            ("image", image.into()),
+            ("site_name", SITE_NAME.into()),
+            (
+                "type",
+                if article.is_some() {
+                    "article"
+                } else {
+                    "website"
+                }
+                .into(),
+            ),
+            ("twitter_card", twitter_card.into()),
+            ("json_ld", page.json_ld(image).into()),
+            ("article", article.into()),
+        ]
+        .into_iter()
+        .collect()
+    }
+}
+
+/// Resolve a site path against the base URL; absolute URLs are kept as they are
+fn absolute_url(base_url: &str, url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blog::BlogPostMeta;
+
+    #[test]
+    fn test_post_meta_overrides() {
+        let meta = BlogPostMeta::new(
+            "Hello".to_string(),
+            "Hi".to_string(),
+            "Ada".to_string(),
+            vec!["rust".to_string()],
+            "2025-01-01 10:00:00",
+            false,
+            false,
+        )
+        .unwrap();
+        let mut post = BlogPost {
+            slug: "hello".to_string(),
+            meta,
+            content: String::new(),
+            html: String::new(),
+        };
+
+        let page = PageMeta::for_post("https://example.com/", &post);
+        assert_eq!(page.url.as_deref(), Some("https://example.com/blog/hello"));
+        let value = Value::from(&page);
+        assert_eq!(value.get("type").unwrap().to_string(), "article");
+        assert_eq!(
+            value.get("twitter_card").unwrap().to_string(),
+            "summary_large_image"
+        );
+        assert_eq!(
+            value.get("image").unwrap().to_string(),
+            "https://example.com/blog/hello/og.png"
+        );
+
+        post.meta.image = Some("/static/banner.png".to_string());
+        post.meta.canonical_url = Some("https://dev.to/ada/hello".to_string());
+        let page = PageMeta::for_post("https://example.com", &post);
+        assert_eq!(page.url.as_deref(), Some("https://dev.to/ada/hello"));
+        assert_eq!(
+            page.image.as_deref(),
+            Some("https://example.com/static/banner.png")
+        );
+        let json_ld = Value::from(&page).get("json_ld").cloned().unwrap();
+        assert_eq!(
+            json_ld.get("url").unwrap().to_string(),
+            "https://dev.to/ada/hello"
+        );
+        assert_eq!(
+            json_ld
+                .get("author")
+                .unwrap()
+                .get("name")
+                .unwrap()
+                .to_string(),
+            "Ada"
+        );
+
+        let page = PageMeta::new("https://example.com", "/404", "Not Found", "").without_url();
+        assert_eq!(Value::from(&page).get("url"), Some(&Value::Null));
+    }
+}
@@ -1,38 +1,126 @@
+use crate::assets::AssetManifest;
 use crate::blog::BlogStore;
+use crate::components::og_image::CardCache;
 use crate::config::Config;
 use crate::templates::TemplateEngine;
 use log::debug;
-use std::sync::OnceLock;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
 
 /// Global blog store instance
 static BLOG_STORE: OnceLock<BlogStore> = OnceLock::new();
 
-/// Global template engine instance
-static TEMPLATE_ENGINE: OnceLock<TemplateEngine> = OnceLock::new();
+/// Global template engine instance, swapped atomically on hot reload
+static TEMPLATE_ENGINE: OnceLock<RwLock<Arc<TemplateEngine>>> = OnceLock::new();
 
-/// Global config instance
-static CONFIG: OnceLock<Config> = OnceLock::new();
+/// Global config instance, swapped atomically on hot reload
+static CONFIG: OnceLock<RwLock<Arc<Config>>> = OnceLock::new();
+
+/// Global asset manifest, rebuilt when static files change
+static ASSETS: OnceLock<RwLock<Arc<AssetManifest>>> = OnceLock::new();
+
+/// Global cache of rendered share cards
+static CARD_CACHE: OnceLock<CardCache> = OnceLock::new();
 
 /// Get the global blog store instance
 pub fn get_blog_store() -> &'static BlogStore {
     BLOG_STORE.get_or_init(|| {
         debug!("Initializing global blog store");
-        BlogStore::new(std::path::PathBuf::from("content"))
+        let config = get_config();
+        BlogStore::new(config.content_dir.clone(), config.drafts)
+    })
+}
+
+/// Fingerprint the static directory, minifying stylesheets outside development
+fn build_assets() -> Result<AssetManifest, Box<dyn std::error::Error + Send + Sync>> {
+    let config = get_config();
+    AssetManifest::build(Path::new(&config.static_dir), !config.is_development())
+}
+
+fn assets_slot() -> &'static RwLock<Arc<AssetManifest>> {
+    ASSETS.get_or_init(|| {
+        debug!("Initializing global asset manifest");
+        let manifest = build_assets().expect("Failed to fingerprint static assets");
+        RwLock::new(Arc::new(manifest))
     })
 }
 
-/// Get the global template engine instance
-pub fn get_template_engine() -> &'static TemplateEngine {
+/// Fingerprint the static files up front so an unreadable file fails startup
+pub fn init_assets() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if ASSETS.get().is_none() {
+        let manifest = build_assets()?;
+        let _ = ASSETS.set(RwLock::new(Arc::new(manifest)));
+    }
+    Ok(())
+}
+
+/// Get the current asset manifest
+pub fn get_assets() -> Arc<AssetManifest> {
+    Arc::clone(&assets_slot().read().unwrap())
+}
+
+/// Rebuild the asset manifest from disk and swap it in
+pub fn reload_assets() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let manifest = Arc::new(build_assets()?);
+    let mut guard = assets_slot().write().unwrap();
+    *guard = manifest;
+    Ok(())
+}
+
+/// Get the global share card cache
+pub fn get_card_cache() -> &'static CardCache {
+    CARD_CACHE.get_or_init(CardCache::default)
+}
+
+fn template_engine_slot() -> &'static RwLock<Arc<TemplateEngine>> {
     TEMPLATE_ENGINE.get_or_init(|| {
         debug!("Initializing global template engine");
-        TemplateEngine::new().expect("Failed to initialize template engine")
+        let engine = TemplateEngine::new(get_config().theme.as_deref())
+            .expect("Failed to initialize template engine");
+        RwLock::new(Arc::new(engine))
     })
 }
 
-/// Get the global config instance
-pub fn get_config() -> &'static Config {
+/// Load the templates up front so a broken template fails startup rather than a request
+pub fn init_template_engine() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if TEMPLATE_ENGINE.get().is_none() {
+        let engine = TemplateEngine::new(get_config().theme.as_deref())?;
+        let _ = TEMPLATE_ENGINE.set(RwLock::new(Arc::new(engine)));
+    }
+    Ok(())
+}
+
+/// Get the current template engine
+pub fn get_template_engine() -> Arc<TemplateEngine> {
+    Arc::clone(&template_engine_slot().read().unwrap())
+}
+
+/// Rebuild the template engine from disk and swap it in
+///
+/// If any template fails to load or validate, the error is returned and the last good
+/// set of templates keeps serving.
+pub fn reload_template_engine() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let engine = Arc::new(TemplateEngine::new(get_config().theme.as_deref())?);
+    let mut guard = template_engine_slot().write().unwrap();
+    *guard = engine;
+    Ok(())
+}
+
+fn config_slot() -> &'static RwLock<Arc<Config>> {
     CONFIG.get_or_init(|| {
         debug!("Initializing global config");
-        Config::from_file_or_env()
+        RwLock::new(Arc::new(Config::from_file_or_env()))
     })
 }
+
+/// Get the current config
+pub fn get_config() -> Arc<Config> {
+    Arc::clone(&config_slot().read().unwrap())
+}
+
+/// Replace the global config
+pub fn set_config(config: Config) {
+    let config = Arc::new(config);
+    let mut guard = config_slot().write().unwrap();
+    *guard = config;
+}
@@ -0,0 +1,517 @@
+//! Allowlist sanitizer for raw HTML written in posts
+//!
+//! Only raw HTML from the markdown source goes through here; markup generated by
+//! nornity itself (shortcodes, code blocks, math) is added afterwards and left alone.
+
+use crate::escape::escape_attr;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Attributes allowed on every allowed tag
+const GLOBAL_ATTRIBUTES: &[&str] = &["class", "id", "title", "lang", "dir"];
+
+/// Tags allowed by default, with the attributes each one may carry
+const DEFAULT_TAGS: &[(&str, &[&str])] = &[
+    ("a", &["href", "rel", "target"]),
+    ("abbr", &[]),
+    ("b", &[]),
+    ("blockquote", &["cite"]),
+    ("br", &[]),
+    ("code", &[]),
+    ("dd", &[]),
+    ("del", &[]),
+    ("details", &["open"]),
+    ("div", &[]),
+    ("dl", &[]),
+    ("dt", &[]),
+    ("em", &[]),
+    ("figcaption", &[]),
+    ("figure", &[]),
+    ("h1", &[]),
+    ("h2", &[]),
+    ("h3", &[]),
+    ("h4", &[]),
+    ("h5", &[]),
+    ("h6", &[]),
+    ("hr", &[]),
+    ("i", &[]),
+    ("img", &["src", "alt", "width", "height", "loading"]),
+    ("ins", &[]),
+    ("kbd", &[]),
+    ("li", &[]),
+    ("mark", &[]),
+    ("ol", &["start"]),
+    ("p", &[]),
+    ("pre", &[]),
+    ("q", &["cite"]),
+    ("s", &[]),
+    ("small", &[]),
+    ("span", &[]),
+    ("strong", &[]),
+    ("sub", &[]),
+    ("summary", &[]),
+    ("sup", &[]),
+    ("table", &[]),
+    ("tbody", &[]),
+    ("td", &["colspan", "rowspan"]),
+    ("tfoot", &[]),
+    ("th", &["colspan", "rowspan", "scope"]),
+    ("thead", &[]),
+    ("tr", &[]),
+    ("u", &[]),
+    ("ul", &[]),
+];
+
+/// Tags whose content is dropped along with the tag
+const DROP_CONTENT: &[&str] = &["script", "style"];
+
+/// Tags that never have content or a closing tag
+const VOID_TAGS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// `rel` values forced onto links that open in another browsing context
+const TARGET_REL: &[&str] = &["noopener", "noreferrer"];
+
+/// Attributes holding URLs, which must use a safe scheme
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "cite", "poster"];
+
+/// URL schemes allowed in URL attributes; scheme-less (relative) URLs are always allowed
+const URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+/// Tags and attributes allowed in raw post HTML
+#[derive(Debug, Clone)]
+pub struct Allowlist {
+    tags: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Default for Allowlist {
+    fn default() -> Self {
+        let tags = DEFAULT_TAGS
+            .iter()
+            .map(|(tag, attributes)| {
+                let attributes = attributes.iter().map(|a| a.to_string()).collect();
+                (tag.to_string(), attributes)
+            })
+            .collect();
+        Self { tags }
+    }
+}
+
+impl Allowlist {
+    /// The default allowlist extended with entries like `iframe[src width height], video`
+    pub fn with_extra(extra: &str) -> Self {
+        let mut allowlist = Self::default();
+        for entry in extra.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (tag, attributes) = match entry.split_once('[') {
+                Some((tag, rest)) => (tag.trim(), rest.trim_end_matches(']')),
+                None => (entry, ""),
+            };
+            allowlist
+                .tags
+                .entry(tag.to_ascii_lowercase())
+                .or_default()
+                .extend(attributes.split_whitespace().map(str::to_ascii_lowercase));
+        }
+        allowlist
+    }
+
+    fn allows_attribute(&self, tag: &str, attribute: &str) -> bool {
+        GLOBAL_ATTRIBUTES.contains(&attribute)
+            || self
+                .tags
+                .get(tag)
+                .is_some_and(|attributes| attributes.contains(attribute))
+    }
+
+    fn write_tag(&self, tag: &Tag, output: &mut String) {
+        output.push('<');
+        output.push_str(&tag.name);
+        let mut rel = None;
+        let mut has_target = false;
+        for (name, value) in &tag.attributes {
+            if !self.allows_attribute(&tag.name, name) {
+                continue;
+            }
+            match value {
+                Some(value) => {
+                    let value = decode_entities(value);
+                    if URL_ATTRIBUTES.contains(&name.as_str()) && !is_safe_url(&value) {
+                        continue;
+                    }
+                    if tag.name == "a" && name == "rel" {
+                        rel = Some(value);
+                        continue;
+                    }
+                    has_target |= tag.name == "a" && name == "target";
+                    output.push_str(&format!(" {name}=\"{}\"", escape_attr(&value)));
+                }
+                None => output.push_str(&format!(" {name}")),
+            }
+        }
+
+        // A kept `target` must not hand the opened page a reference back to this one
+        let mut rel: Vec<&str> = rel.as_deref().unwrap_or("").split_whitespace().collect();
+        if has_target {
+            for value in TARGET_REL {
+                if !rel.contains(value) {
+                    rel.push(value);
+                }
+            }
+        }
+        if !rel.is_empty() {
+            output.push_str(&format!(" rel=\"{}\"", escape_attr(&rel.join(" "))));
+        }
+        output.push_str(if tag.self_closing { " />" } else { ">" });
+        // Browsers ignore the `/` of `<div />` and leave it open, so close it here
+        if tag.self_closing && !VOID_TAGS.contains(&tag.name.as_str()) {
+            output.push_str(&format!("</{}>", tag.name));
+        }
+    }
+}
+
+/// Sanitizes the raw HTML fragments of one document, keeping allowed tags balanced
+///
+/// Markdown splits raw HTML into many fragments, so elements stay open across them.
+/// Each element belongs to the markdown container it was opened in: a closing tag
+/// only matches an element of the current container, and the elements still open
+/// when a container ends are closed with it. Unmatched closing tags are dropped.
+pub struct Sanitizer<'a> {
+    allowlist: &'a Allowlist,
+    /// Open elements with the container depth they were opened at, innermost last
+    open: Vec<(String, usize)>,
+    depth: usize,
+}
+
+impl<'a> Sanitizer<'a> {
+    pub fn new(allowlist: &'a Allowlist) -> Self {
+        Self {
+            allowlist,
+            open: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Sanitize the next fragment
+    ///
+    /// Allowed tags are re-emitted with only their allowed attributes. Other tags are
+    /// dropped but their text is kept, except for `<script>` and `<style>` whose content
+    /// goes too. Comments are removed and stray `<` is escaped.
+    pub fn sanitize(&mut self, html: &str) -> String {
+        let mut output = String::with_capacity(html.len());
+        let mut rest = html;
+        // Closing tag that ends a dropped `<script>` or `<style>` element
+        let mut skip_until: Option<String> = None;
+
+        while let Some(start) = rest.find('<') {
+            if skip_until.is_none() {
+                output.push_str(&rest[..start].replace('>', "&gt;"));
+            }
+            rest = &rest[start..];
+
+            if let Some(end) = rest.strip_prefix("<!--").map(|r| r.find("-->")) {
+                // Comments, including unterminated ones, are dropped
+                rest = end.map_or("", |end| &rest[4 + end + 3..]);
+                continue;
+            }
+
+            let Some((tag, consumed)) = parse_tag(rest) else {
+                if skip_until.is_none() {
+                    output.push_str("&lt;");
+                }
+                rest = &rest[1..];
+                continue;
+            };
+            rest = &rest[consumed..];
+
+            if let Some(closing) = &skip_until {
+                if tag.closing && &tag.name == closing {
+                    skip_until = None;
+                }
+                continue;
+            }
+            if DROP_CONTENT.contains(&tag.name.as_str()) {
+                if !tag.closing && !tag.self_closing {
+                    skip_until = Some(tag.name);
+                }
+                continue;
+            }
+            if !self.allowlist.tags.contains_key(&tag.name) {
+                continue;
+            }
+            if tag.closing {
+                self.close(&tag.name, &mut output);
+            } else {
+                self.allowlist.write_tag(&tag, &mut output);
+                if !tag.self_closing && !VOID_TAGS.contains(&tag.name.as_str()) {
+                    self.open.push((tag.name, self.depth));
+                }
+            }
+        }
+
+        if skip_until.is_none() {
+            output.push_str(&rest.replace('>', "&gt;"));
+        }
+        output
+    }
+
+    /// A markdown container starts
+    pub fn enter(&mut self) {
+        self.depth += 1;
+    }
+
+    /// A markdown container ends, returning the closing tags of the elements left open in it
+    pub fn leave(&mut self) -> String {
+        let closing = self.close_from(self.depth);
+        self.depth = self.depth.saturating_sub(1);
+        closing
+    }
+
+    /// The document ends, returning the closing tags of every element still open
+    pub fn finish(&mut self) -> String {
+        self.depth = 0;
+        self.close_from(0)
+    }
+
+    /// Close the innermost open `name` of the current container and everything inside it
+    fn close(&mut self, name: &str, output: &mut String) {
+        let Some(index) = self
+            .open
+            .iter()
+            .rposition(|(open, depth)| open == name && *depth == self.depth)
+        else {
+            return;
+        };
+        for (open, _) in self.open.drain(index..).rev() {
+            output.push_str(&format!("</{open}>"));
+        }
+    }
+
+    /// Close the elements opened at `depth` or deeper
+    fn close_from(&mut self, depth: usize) -> String {
+        let index = self
+            .open
+            .iter()
+            .position(|(_, opened)| *opened >= depth)
+            .unwrap_or(self.open.len());
+        self.open
+            .drain(index..)
+            .rev()
+            .map(|(open, _)| format!("</{open}>"))
+            .collect()
+    }
+}
+
+/// A parsed start or end tag
+struct Tag {
+    name: String,
+    closing: bool,
+    self_closing: bool,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+/// Parse a tag at the start of `input`, returning it and the number of bytes it spans
+fn parse_tag(input: &str) -> Option<(Tag, usize)> {
+    let mut rest = input.strip_prefix('<')?;
+    let closing = rest.starts_with('/');
+    if closing {
+        rest = &rest[1..];
+    }
+
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = rest[..name_len].to_ascii_lowercase();
+    rest = &rest[name_len..];
+
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            let tag = Tag {
+                name,
+                closing,
+                self_closing: true,
+                attributes,
+            };
+            return Some((tag, input.len() - after.len()));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            let tag = Tag {
+                name,
+                closing,
+                self_closing: false,
+                attributes,
+            };
+            return Some((tag, input.len() - after.len()));
+        }
+
+        let attr_len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/' | '"' | '\''))
+            .unwrap_or(rest.len());
+        if attr_len == 0 {
+            // A stray `/` or quote inside the tag; skip it
+            let mut chars = rest.chars();
+            chars.next()?;
+            rest = chars.as_str();
+            continue;
+        }
+        let attr_name = rest[..attr_len].to_ascii_lowercase();
+        rest = rest[attr_len..].trim_start();
+
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(quote)?;
+                    (&body[..end], &body[end + 1..])
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = remaining;
+            Some(value.to_string())
+        } else {
+            None
+        };
+        attributes.push((attr_name, value));
+    }
+}
+
+/// Decode the character references that can hide characters in attribute values
+///
+/// Anything not decoded here keeps its `&`, which is escaped again on output, so an
+/// undecoded reference can only ever show up as literal text.
+fn decode_entities(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity.strip_prefix('#').and_then(|number| {
+                    let code = match number.strip_prefix(['x', 'X']) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => number.parse().ok(),
+                    };
+                    code.and_then(char::from_u32)
+                }),
+            };
+            c.map(|c| (c, end + 1))
+        });
+
+        match decoded {
+            Some((c, consumed)) => {
+                output.push(c);
+                rest = &rest[consumed..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// Whether a URL is relative or uses an allowed scheme
+pub fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside the scheme
+    let compact: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let scheme_end = compact.find([':', '/', '?', '#']);
+    match scheme_end {
+        Some(end) if compact[end..].starts_with(':') => URL_SCHEMES.contains(&&compact[..end]),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sanitize a standalone fragment, closing whatever it leaves open
+    fn sanitize(allowlist: &Allowlist, html: &str) -> String {
+        let mut sanitizer = Sanitizer::new(allowlist);
+        sanitizer.sanitize(html) + &sanitizer.finish()
+    }
+
+    #[test]
+    fn test_sanitize_allowlist() {
+        let allowlist = Allowlist::default();
+        let clean = |html| sanitize(&allowlist, html);
+
+        assert_eq!(
+            clean(r#"<b onclick="x()">bold</b><script>alert(1)</script>"#),
+            "<b>bold</b>"
+        );
+        assert_eq!(
+            clean(r#"<img src=x onerror=alert(1) alt='a "b"'>"#),
+            r#"<img src="x" alt="a &quot;b&quot;">"#
+        );
+        assert_eq!(
+            clean(r#"<a href="jav&#x09;ascript&#58;alert(1)" class="c">x</a>"#),
+            r#"<a class="c">x</a>"#
+        );
+        assert_eq!(
+            clean(r#"<a href="https://example.com/?a=1&amp;b=2">x</a>"#),
+            r#"<a href="https://example.com/?a=1&amp;b=2">x</a>"#
+        );
+        assert_eq!(clean("<iframe src=x></iframe>text<!-- c -->"), "text");
+        assert_eq!(clean("1 < 2 > 0"), "1 &lt; 2 &gt; 0");
+
+        let extended = Allowlist::with_extra("iframe[src], video");
+        assert_eq!(
+            sanitize(
+                &extended,
+                r#"<iframe src="https://v.example" onload=x></iframe>"#
+            ),
+            r#"<iframe src="https://v.example"></iframe>"#
+        );
+    }
+
+    #[test]
+    fn test_sanitize_balances_tags() {
+        let allowlist = Allowlist::default();
+        let clean = |html| sanitize(&allowlist, html);
+
+        assert_eq!(clean("<p>x</p></div></div>"), "<p>x</p>");
+        assert_eq!(clean("<div><span>x</div>y"), "<div><span>x</span></div>y");
+        assert_eq!(clean("<div><b>x"), "<div><b>x</b></div>");
+        assert_eq!(clean("a<br>b<div/>c<hr />"), "a<br>b<div /></div>c<hr />");
+        assert_eq!(
+            clean(r#"<a href="/x" target="_blank" rel="nofollow">x</a>"#),
+            r#"<a href="/x" target="_blank" rel="nofollow noopener noreferrer">x</a>"#
+        );
+        assert_eq!(clean(r#"<a rel="me">x</a>"#), r#"<a rel="me">x</a>"#);
+
+        // Elements belong to the markdown container they were opened in
+        let mut sanitizer = Sanitizer::new(&allowlist);
+        assert_eq!(sanitizer.sanitize("<div>"), "<div>");
+        sanitizer.enter();
+        assert_eq!(sanitizer.sanitize("<b></div>"), "<b>");
+        assert_eq!(sanitizer.leave(), "</b>");
+        assert_eq!(sanitizer.sanitize("</span></div></div>"), "</div>");
+        assert_eq!(sanitizer.finish(), "");
+    }
+}
@@ -1,6 +1,8 @@
+use crate::assets;
 use crate::config::Config;
 use crate::handlers;
-use axum::{Router, routing::get};
+use crate::live_reload;
+use axum::{Router, middleware, routing::get};
 use log::{debug, error, info};
 use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
@@ -8,7 +10,9 @@ use tower_http::services::ServeDir;
 /// Build the application router
 pub fn build_app(config: &Config) -> Router {
     info!("Building application router");
-    let static_service = ServeDir::new(&config.static_dir);
+    let static_service = Router::new()
+        .fallback_service(ServeDir::new(&config.static_dir))
+        .layer(middleware::from_fn(assets::serve_fingerprinted));
     debug!(
         "Static file service configured for directory: {}",
         config.static_dir
@@ -18,13 +22,16 @@ pub fn build_app(config: &Config) -> Router {
         .route("/", get(handlers::homepage))
         .route("/blog", get(handlers::blog_list))
         .route("/blog/{slug}", get(handlers::blog_post))
+        .route("/blog/{slug}/og.png", get(handlers::og_image))
         .route("/sitemap.xml", get(handlers::sitemap))
         .route("/robots.txt", get(handlers::robots_txt))
         .route("/rss.xml", get(handlers::rss_feed))
-        .nest_service("/static", static_service)
+        .nest(assets::STATIC_PREFIX, static_service)
         .fallback(handlers::not_found);
 
-    info!("Router configured with {} routes", 6);
+    let router = live_reload::mount(router, config);
+
+    info!("Router configured with {} routes", 7);
     router
 }
 
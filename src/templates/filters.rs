@@ -0,0 +1,195 @@
+//! Built-in template filters
+
+use super::value::Value;
+use crate::escape::escape_attr;
+use std::fmt::Write;
+
+/// Filter names accepted by the parser
+const FILTERS: &[&str] = &[
+    "date",
+    "truncate",
+    "escape",
+    "e",
+    "safe",
+    "length",
+    "join",
+    "default",
+    "upper",
+    "lower",
+    "urlencode",
+    "sort",
+    "json",
+];
+
+/// Check whether a filter exists
+pub fn exists(name: &str) -> bool {
+    FILTERS.contains(&name)
+}
+
+/// Apply a filter to a value
+pub fn apply(name: &str, input: Value, args: &[Value]) -> Result<Value, String> {
+    match name {
+        "date" => date(input, args.first()),
+        "truncate" => truncate(input, args.first()),
+        "escape" | "e" => Ok(match input {
+            Value::Safe(_) => input,
+            other => Value::Safe(escape_attr(&other.to_string())),
+        }),
+        "safe" => Ok(match input {
+            Value::Safe(_) => input,
+            other => Value::Safe(other.to_string()),
+        }),
+        "length" => match &input {
+            Value::List(items) => Ok(items.len().into()),
+            Value::Map(map) => Ok(map.len().into()),
+            Value::String(s) | Value::Safe(s) => Ok(s.chars().count().into()),
+            Value::Null => Ok(Value::Int(0)),
+            other => Err(format!("length: cannot measure a {}", other.type_name())),
+        },
+        "join" => {
+            let separator = args.first().map(Value::to_string).unwrap_or_default();
+            match input {
+                Value::List(items) => Ok(Value::String(
+                    items
+                        .iter()
+                        .map(Value::to_string)
+                        .collect::<Vec<_>>()
+                        .join(&separator),
+                )),
+                other => Ok(other),
+            }
+        }
+        "default" => {
+            if input.is_truthy() || matches!(input, Value::Bool(false) | Value::Int(0)) {
+                Ok(input)
+            } else {
+                Ok(args.first().cloned().unwrap_or_default())
+            }
+        }
+        "upper" => Ok(Value::String(input.to_string().to_uppercase())),
+        "lower" => Ok(Value::String(input.to_string().to_lowercase())),
+        "urlencode" => Ok(Value::String(url_encode(&input.to_string()))),
+        "sort" => match input {
+            Value::List(mut items) => {
+                items.sort_by_key(|item| item.to_string());
+                Ok(Value::List(items))
+            }
+            other => Err(format!("sort: cannot sort a {}", other.type_name())),
+        },
+        "json" => {
+            let mut json = String::new();
+            write_json(&input, &mut json);
+            Ok(Value::Safe(json))
+        }
+        _ => Err(format!("unknown filter '{name}'")),
+    }
+}
+
+/// `date(format)`: format a date with chrono's `strftime` syntax
+fn date(input: Value, format: Option<&Value>) -> Result<Value, String> {
+    let format = format
+        .map(Value::to_string)
+        .unwrap_or_else(|| "%B %d, %Y".to_string());
+    match input {
+        Value::Date(date) => {
+            let mut formatted = String::new();
+            write!(formatted, "{}", date.format(&format))
+                .map_err(|_| format!("date: invalid format '{format}'"))?;
+            Ok(Value::String(formatted))
+        }
+        Value::Null => Ok(Value::Null),
+        other => Err(format!(
+            "date: expected a date, got a {}",
+            other.type_name()
+        )),
+    }
+}
+
+/// `truncate(length)`: shorten text to at most `length` characters, adding an ellipsis
+fn truncate(input: Value, length: Option<&Value>) -> Result<Value, String> {
+    let length = match length {
+        Some(Value::Int(n)) if *n >= 0 => *n as usize,
+        Some(other) => return Err(format!("truncate: invalid length '{other}'")),
+        None => 255,
+    };
+    let text = input.to_string();
+    if text.chars().count() <= length {
+        return Ok(Value::String(text));
+    }
+    let cut: String = text.chars().take(length).collect();
+    Ok(Value::String(format!("{}…", cut.trim_end())))
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters
+pub fn url_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => {
+                let _ = write!(encoded, "%{byte:02X}");
+            }
+        }
+    }
+    encoded
+}
+
+/// Serialize a value as JSON that is safe to embed in a `<script>` element
+///
+/// Dates become RFC 3339 strings. `<`, `>` and `&` are escaped as `\u` sequences so the
+/// output can never close the surrounding script.
+fn write_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => {
+            let _ = write!(out, "{b}");
+        }
+        Value::Int(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::String(s) | Value::Safe(s) => write_json_string(s, out),
+        Value::Date(date) => write_json_string(&date.to_rfc3339(), out),
+        Value::List(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json(item, out);
+            }
+            out.push(']');
+        }
+        Value::Map(map) => {
+            out.push('{');
+            for (i, (key, item)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_string(key, out);
+                out.push(':');
+                write_json(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_json_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() || matches!(c, '<' | '>' | '&' | '\u{2028}' | '\u{2029}') => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
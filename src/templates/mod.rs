@@ -0,0 +1,370 @@
+//! HTML template engine
+//!
+//! Templates are parsed once when the engine is built; see [`parser`] for the syntax.
+//! Printed values are HTML-escaped unless they are marked safe.
+//!
+//! Templates come from three layers, later ones replacing earlier ones by name:
+//! the defaults compiled into the binary, every `.html` file under [`TEMPLATE_DIR`],
+//! and the selected theme under [`THEMES_DIR`].
+
+mod filters;
+mod parser;
+mod render;
+mod value;
+
+pub use value::{Context, Value};
+
+use crate::live_reload;
+use log::{debug, error};
+use parser::Nodes;
+use render::Renderer;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory site templates are loaded from
+pub const TEMPLATE_DIR: &str = "templates";
+
+/// Directory holding installed themes, one subdirectory per theme
+pub const THEMES_DIR: &str = "themes";
+
+/// Default templates compiled into the binary, so a site only needs the ones it changes
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("base.html", include_str!("../../templates/base.html")),
+    (
+        "homepage.html",
+        include_str!("../../templates/homepage.html"),
+    ),
+    (
+        "blog_list.html",
+        include_str!("../../templates/blog_list.html"),
+    ),
+    (
+        "blog_post.html",
+        include_str!("../../templates/blog_post.html"),
+    ),
+    ("404.html", include_str!("../../templates/404.html")),
+    (
+        "components/fab.html",
+        include_str!("../../templates/components/fab.html"),
+    ),
+    (
+        "components/footer.html",
+        include_str!("../../templates/components/footer.html"),
+    ),
+    (
+        "components/blog_preview.html",
+        include_str!("../../templates/components/blog_preview.html"),
+    ),
+    (
+        "components/tags.html",
+        include_str!("../../templates/components/tags.html"),
+    ),
+];
+
+/// Template engine holding every parsed template
+pub struct TemplateEngine {
+    templates: HashMap<String, Nodes>,
+}
+
+impl TemplateEngine {
+    /// Create a new template engine from the built-in defaults, `templates/` and a theme
+    ///
+    /// Fails if the theme is not installed, a template does not parse, or a template
+    /// extends or includes one that does not exist.
+    pub fn new(theme: Option<&str>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        // Template name -> (where it came from, source)
+        let mut sources: HashMap<String, (String, String)> = BUILTIN_TEMPLATES
+            .iter()
+            .map(|(name, source)| {
+                (
+                    name.to_string(),
+                    (format!("built-in {name}"), source.to_string()),
+                )
+            })
+            .collect();
+
+        let template_dir = Path::new(TEMPLATE_DIR);
+        if template_dir.is_dir() {
+            discover(template_dir, &mut sources)?;
+        }
+        if let Some(theme) = theme {
+            let theme_dir = theme_dir(theme);
+            if !theme_dir.is_dir() {
+                return Err(format!("Theme '{theme}' not found in {THEMES_DIR}/").into());
+            }
+            discover(&theme_dir, &mut sources)?;
+        }
+
+        let mut templates = HashMap::new();
+        for (name, (origin, source)) in sources {
+            let nodes = parser::parse(&source).map_err(|e| format!("Template {origin}:{e}"))?;
+            debug!("Loaded template: {name} ({origin})");
+            templates.insert(name, nodes);
+        }
+
+        Self::from_parsed(templates)
+    }
+
+    /// Build an engine from parsed templates, checking that every include and parent resolves
+    fn from_parsed(
+        templates: HashMap<String, Nodes>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        for (template_name, nodes) in &templates {
+            for (dependency, line) in parser::dependencies(nodes) {
+                if !templates.contains_key(&dependency) {
+                    return Err(format!(
+                        "Template {template_name}:{line}: template '{dependency}' not found"
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(Self { templates })
+    }
+
+    /// Paths of the stylesheets any template inlines with `inline_css`, sorted
+    #[cfg_attr(not(feature = "hot-reload"), allow(dead_code))]
+    pub fn inlined_stylesheets(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .templates
+            .values()
+            .flat_map(|nodes| parser::inlined_stylesheets(nodes))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
+    /// Render a template with a context
+    ///
+    /// Besides the context, every template can use `live_reload`, the development reload script.
+    pub fn render(
+        &self,
+        template_name: &str,
+        context: &Context,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let mut globals = Context::new();
+        globals.insert("live_reload", Value::safe(live_reload::script()));
+
+        let mut output = String::new();
+        Renderer::new(&self.templates, context, &globals)
+            .render_template(template_name, &mut output)?;
+        Ok(output)
+    }
+}
+
+/// Directory of an installed theme
+fn theme_dir(theme: &str) -> PathBuf {
+    Path::new(THEMES_DIR).join(theme)
+}
+
+/// Read every `.html` file under `root`, naming each by its path relative to `root`
+fn discover(
+    root: &Path,
+    sources: &mut HashMap<String, (String, String)>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| {
+            error!("Failed to read template directory {}: {e}", dir.display());
+            format!("Failed to read template directory {}: {e}", dir.display())
+        })?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            if path.extension().is_none_or(|ext| ext != "html") {
+                continue;
+            }
+
+            let name = path
+                .strip_prefix(root)?
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let source = fs::read_to_string(&path).map_err(|e| {
+                error!("Failed to load template {}: {e}", path.display());
+                format!("Failed to load template {}: {e}", path.display())
+            })?;
+            sources.insert(name, (path.display().to_string(), source));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn engine(
+        templates: &[(&str, &str)],
+    ) -> Result<TemplateEngine, Box<dyn std::error::Error + Send + Sync>> {
+        let mut parsed = HashMap::new();
+        for (name, source) in templates {
+            let nodes = parser::parse(source).map_err(|e| format!("Template {name}:{e}"))?;
+            parsed.insert(name.to_string(), nodes);
+        }
+        TemplateEngine::from_parsed(parsed)
+    }
+
+    #[test]
+    fn test_render_loops_conditionals_and_filters() {
+        let engine = engine(&[
+            (
+                "list.html",
+                "{% for post in posts %}{% if not loop.first %}, {% endif %}\
+                 {{ post.title | truncate(5) }} ({{ post.date | date(\"%Y\") }})\
+                 {% else %}none{% endfor %}{% include \"count.html\" %}",
+            ),
+            (
+                "count.html",
+                " [{{ posts | length }}{% if posts | length > 1 %} posts{% endif %}]",
+            ),
+        ])
+        .unwrap();
+
+        let date = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let posts: Vec<Value> = ["Hello world", "Tiny"]
+            .into_iter()
+            .map(|title| Value::from_iter([("title", Value::from(title)), ("date", date.into())]))
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("posts", posts);
+        assert_eq!(
+            engine.render("list.html", &context).unwrap(),
+            "Hello… (2024), Tiny (2024) [2 posts]"
+        );
+
+        context.insert("posts", Vec::<Value>::new());
+        assert_eq!(engine.render("list.html", &context).unwrap(), "none [0]");
+    }
+
+    #[test]
+    fn test_autoescape_and_safe() {
+        let engine = engine(&[(
+            "page.html",
+            "<p title=\"{{ text }}\">{{ text }}{{ html }}{{ text | safe }}</p>",
+        )])
+        .unwrap();
+        let mut context = Context::new();
+        context.insert("text", "<b>\"Tom & Jerry\"</b>");
+        context.insert("html", Value::safe("<i>ok</i>"));
+
+        assert_eq!(
+            engine.render("page.html", &context).unwrap(),
+            "<p title=\"&lt;b&gt;&quot;Tom &amp; Jerry&quot;&lt;/b&gt;\">\
+             &lt;b&gt;&quot;Tom &amp; Jerry&quot;&lt;/b&gt;<i>ok</i><b>\"Tom & Jerry\"</b></p>"
+        );
+    }
+
+    #[test]
+    fn test_extends_blocks_and_include_with() {
+        let engine = engine(&[
+            (
+                "base.html",
+                "<title>{% block title %}Site{% endblock %}</title>\
+                 {% block head %}{% endblock %}<main>{% block content %}{% endblock %}</main>",
+            ),
+            (
+                "page.html",
+                "{% extends \"base.html\" %}\n\
+                 {% block title %}{{ name }}{% endblock %}\n\
+                 {% block content %}{% include \"greet.html\" with who=name | upper, punct=\"!\" %}\
+                 {% block inner %}default{% endblock %}{% endblock %}",
+            ),
+            (
+                "child.html",
+                "{% extends \"page.html\" %}{% block inner %}override{% endblock %}",
+            ),
+            ("greet.html", "Hi {{ who }}{{ punct }} "),
+        ])
+        .unwrap();
+
+        let mut context = Context::new();
+        context.insert("name", "ada");
+        assert_eq!(
+            engine.render("page.html", &context).unwrap(),
+            "<title>ada</title><main>Hi ADA! default</main>"
+        );
+        assert_eq!(
+            engine.render("child.html", &context).unwrap(),
+            "<title>ada</title><main>Hi ADA! override</main>"
+        );
+    }
+
+    #[test]
+    fn test_discover_layers_templates() {
+        let dir = std::env::temp_dir().join(format!("nornity-templates-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("components")).unwrap();
+        std::fs::write(dir.join("blog_post.html"), "themed").unwrap();
+        std::fs::write(dir.join("components/extra.html"), "extra").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let mut sources: HashMap<String, (String, String)> = BUILTIN_TEMPLATES
+            .iter()
+            .map(|(name, source)| (name.to_string(), (String::new(), source.to_string())))
+            .collect();
+        discover(&dir, &mut sources).unwrap();
+
+        assert_eq!(sources["blog_post.html"].1, "themed");
+        assert_eq!(sources["components/extra.html"].1, "extra");
+        assert!(sources.contains_key("base.html"));
+        assert!(!sources.contains_key("notes.txt"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_inlined_stylesheets() {
+        let engine = engine(&[
+            ("base.html", "{% block head %}{% endblock %}"),
+            (
+                "post.html",
+                "{% extends \"base.html\" %}{% block head %}<style>{{ inline_css \"css/post.css\" }}</style>\
+                 <link href=\"{{ asset \"css/style.css\" }}\">{% endblock %}",
+            ),
+            (
+                "page.html",
+                "{% if wide %}<style>{{ inline_css \"css/wide.css\" }}</style>{% endif %}\
+                 {% include \"base.html\" with css=inline_css \"css/post.css\" %}",
+            ),
+        ])
+        .unwrap();
+        assert_eq!(
+            engine.inlined_stylesheets(),
+            ["css/post.css", "css/wide.css"]
+        );
+        assert!(
+            TemplateEngine::new(None)
+                .unwrap()
+                .inlined_stylesheets()
+                .contains(&"css/blog-post.css".to_string())
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let err = engine(&[("bad.html", "<p>\n{{ title</p>")]).err().unwrap();
+        assert!(err.to_string().contains("bad.html:2"));
+        assert!(engine(&[("bad.html", "{{ a {{ b }}")]).is_err());
+        assert!(engine(&[("bad.html", "{% if x %}open")]).is_err());
+        assert!(engine(&[("bad.html", "{{ x | nope }}")]).is_err());
+        assert!(engine(&[("bad.html", "{% include \"missing.html\" %}")]).is_err());
+        assert!(engine(&[("bad.html", "{% extends \"missing.html\" %}")]).is_err());
+        assert!(engine(&[("a.html", "x{% extends \"b.html\" %}"), ("b.html", "")]).is_err());
+        assert!(
+            engine(&[(
+                "bad.html",
+                "{% block a %}{% endblock %}{% block a %}{% endblock %}"
+            )])
+            .is_err()
+        );
+        assert!(engine(&[("bad.html", "{% block a %}open")]).is_err());
+    }
+}
@@ -0,0 +1,816 @@
+//! Template syntax
+//!
+//! - `{{ expr }}` prints an expression, escaped unless it is marked safe
+//! - `{% if expr %}`, `{% elif expr %}`, `{% else %}`, `{% endif %}`
+//! - `{% for item in expr %}` ... `{% else %}` (when empty) ... `{% endfor %}`
+//! - `{% include "components/footer.html" %}`, optionally `with name=expr, other=expr`
+//! - `{% extends "base.html" %}` as the first tag, then `{% block name %}` ... `{% endblock %}`
+//!   to replace the parent's block of the same name
+//! - `{# comment #}`
+//!
+//! A `-` just inside a delimiter (`{%-`, `-%}`) trims whitespace on that side.
+//! Expressions support paths (`post.title`), string and integer literals, `true`,
+//! `false`, `none`, filters (`| date("%Y")`), `not`, `and`, `or`, `in` and comparisons.
+//! `asset "css/style.css"` gives the fingerprinted URL of a static file, and
+//! `inline_css "css/post.css"` the bundled stylesheet itself, for a `<style>` tag.
+
+use super::filters;
+use super::value::Value;
+
+/// A parsed template body
+pub type Nodes = Vec<Node>;
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Text(String),
+    Output {
+        expr: Expr,
+        line: usize,
+    },
+    If {
+        branches: Vec<(Expr, Nodes)>,
+        otherwise: Nodes,
+        line: usize,
+    },
+    For {
+        var: String,
+        iterable: Expr,
+        body: Nodes,
+        empty: Nodes,
+        line: usize,
+    },
+    Include {
+        name: String,
+        /// Variables set for the included template only
+        with: Vec<(String, Expr)>,
+        line: usize,
+    },
+    Extends {
+        name: String,
+        line: usize,
+    },
+    Block {
+        name: String,
+        body: Nodes,
+        line: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Literal(Value),
+    Path(Vec<String>),
+    Filter {
+        input: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    Not(Box<Expr>),
+    /// Fingerprinted URL of a static file, by its path under the static directory
+    Asset(String),
+    /// Bundled contents of a stylesheet, by its path under the static directory
+    InlineCss(String),
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+}
+
+/// Parse a template, reporting errors as `<line>: <message>`
+pub fn parse(source: &str) -> Result<Nodes, String> {
+    let segments = split_segments(source)?;
+    let mut parser = BlockParser {
+        segments,
+        position: 0,
+    };
+    let (nodes, end) = parser.parse_until(&[])?;
+    if let Some(tag) = end {
+        return Err(format!("{}: unexpected {{% {} %}}", tag.line, tag.keyword));
+    }
+
+    check_extends(&nodes)?;
+    let mut seen = Vec::new();
+    let mut duplicate = None;
+    walk(&nodes, &mut |node| {
+        if let Node::Block { name, line, .. } = node {
+            if seen.contains(&name) {
+                duplicate.get_or_insert(format!("{line}: block '{name}' is defined twice"));
+            }
+            seen.push(name);
+        }
+    });
+    match duplicate {
+        Some(error) => Err(error),
+        None => Ok(nodes),
+    }
+}
+
+/// An extending template must start with `{% extends %}` and hold nothing but blocks
+fn check_extends(nodes: &[Node]) -> Result<(), String> {
+    let mut content = nodes.iter().filter(|node| !is_blank(node));
+    let Some(Node::Extends { .. }) = content.next() else {
+        return match nodes
+            .iter()
+            .find(|node| matches!(node, Node::Extends { .. }))
+        {
+            Some(Node::Extends { line, .. }) => {
+                Err(format!("{line}: {{% extends %}} must be the first tag"))
+            }
+            _ => Ok(()),
+        };
+    };
+
+    for node in content {
+        match node {
+            Node::Block { .. } => {}
+            Node::Extends { line, .. } => {
+                return Err(format!("{line}: a template can only extend one parent"));
+            }
+            Node::Output { line, .. }
+            | Node::If { line, .. }
+            | Node::For { line, .. }
+            | Node::Include { line, .. } => {
+                return Err(format!(
+                    "{line}: only blocks are allowed in a template that extends another"
+                ));
+            }
+            Node::Text(_) => {
+                return Err("text outside of blocks in a template that extends another".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_blank(node: &Node) -> bool {
+    matches!(node, Node::Text(text) if text.trim().is_empty())
+}
+
+/// Name of the parent template, if this template extends one
+pub fn parent(nodes: &[Node]) -> Option<&str> {
+    nodes.iter().find_map(|node| match node {
+        Node::Extends { name, .. } => Some(name.as_str()),
+        _ => None,
+    })
+}
+
+/// Every block defined in a template, including nested ones
+pub fn blocks(nodes: &[Node]) -> Vec<(&str, &[Node])> {
+    let mut found = Vec::new();
+    walk(nodes, &mut |node| {
+        if let Node::Block { name, body, .. } = node {
+            found.push((name.as_str(), body.as_slice()));
+        }
+    });
+    found
+}
+
+/// Names of all templates a parsed template extends or includes
+pub fn dependencies(nodes: &[Node]) -> Vec<(String, usize)> {
+    let mut found = Vec::new();
+    walk(nodes, &mut |node| match node {
+        Node::Include { name, line, .. } | Node::Extends { name, line } => {
+            found.push((name.clone(), *line))
+        }
+        _ => {}
+    });
+    found
+}
+
+/// Paths of all stylesheets a parsed template inlines with `inline_css`
+pub fn inlined_stylesheets(nodes: &[Node]) -> Vec<String> {
+    fn visit_expr(expr: &Expr, found: &mut Vec<String>) {
+        match expr {
+            Expr::InlineCss(path) => found.push(path.clone()),
+            Expr::Filter { input, args, .. } => {
+                visit_expr(input, found);
+                for arg in args {
+                    visit_expr(arg, found);
+                }
+            }
+            Expr::Not(inner) => visit_expr(inner, found),
+            Expr::Binary { left, right, .. } => {
+                visit_expr(left, found);
+                visit_expr(right, found);
+            }
+            Expr::Literal(_) | Expr::Path(_) | Expr::Asset(_) => {}
+        }
+    }
+
+    let mut found = Vec::new();
+    walk(nodes, &mut |node| match node {
+        Node::Output { expr, .. } => visit_expr(expr, &mut found),
+        Node::If { branches, .. } => {
+            for (condition, _) in branches {
+                visit_expr(condition, &mut found);
+            }
+        }
+        Node::For { iterable, .. } => visit_expr(iterable, &mut found),
+        Node::Include { with, .. } => {
+            for (_, expr) in with {
+                visit_expr(expr, &mut found);
+            }
+        }
+        Node::Text(_) | Node::Extends { .. } | Node::Block { .. } => {}
+    });
+    found
+}
+
+/// Visit every node, depth first
+fn walk<'a>(nodes: &'a [Node], visit: &mut impl FnMut(&'a Node)) {
+    for node in nodes {
+        visit(node);
+        match node {
+            Node::If {
+                branches,
+                otherwise,
+                ..
+            } => {
+                for (_, body) in branches {
+                    walk(body, visit);
+                }
+                walk(otherwise, visit);
+            }
+            Node::For { body, empty, .. } => {
+                walk(body, visit);
+                walk(empty, visit);
+            }
+            Node::Block { body, .. } => walk(body, visit),
+            Node::Text(_) | Node::Output { .. } | Node::Include { .. } | Node::Extends { .. } => {}
+        }
+    }
+}
+
+/// Raw pieces of a template before block structure is resolved
+#[derive(Debug)]
+enum Segment {
+    Text(String),
+    Output(String, usize),
+    Tag(String, usize),
+}
+
+/// Split source into text, `{{ }}` and `{% %}` segments, dropping comments
+fn split_segments(source: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    let mut trim_next = false;
+
+    loop {
+        let next = ["{{", "{%", "{#"]
+            .iter()
+            .filter_map(|open| rest.find(open).map(|at| (at, *open)))
+            .min_by_key(|(at, _)| *at);
+
+        let Some((start, open)) = next else {
+            push_text(&mut segments, rest, trim_next, false);
+            break;
+        };
+
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let after_open = &rest[start + 2..];
+        let end = after_open
+            .find(close)
+            .ok_or_else(|| format!("{}: unclosed '{open}'", line + count_lines(&rest[..start])))?;
+
+        let mut inner = &after_open[..end];
+        let trim_before = inner.starts_with('-');
+        if trim_before {
+            inner = &inner[1..];
+        }
+        let trim_after = inner.ends_with('-');
+        if trim_after {
+            inner = &inner[..inner.len() - 1];
+        }
+
+        push_text(&mut segments, &rest[..start], trim_next, trim_before);
+        line += count_lines(&rest[..start]);
+        let inner = inner.trim();
+
+        if inner.contains(open) {
+            return Err(format!("{line}: '{open}' opened inside another '{open}'"));
+        }
+        match open {
+            "{{" if inner.is_empty() => return Err(format!("{line}: empty '{{{{ }}}}'")),
+            "{{" => segments.push(Segment::Output(inner.to_string(), line)),
+            "{%" => segments.push(Segment::Tag(inner.to_string(), line)),
+            _ => {}
+        }
+
+        let consumed = start + 2 + end + close.len();
+        line += count_lines(&rest[start..consumed]);
+        rest = &rest[consumed..];
+        trim_next = trim_after;
+    }
+
+    Ok(segments)
+}
+
+fn push_text(segments: &mut Vec<Segment>, text: &str, trim_start: bool, trim_end: bool) {
+    let text = if trim_start { text.trim_start() } else { text };
+    let text = if trim_end { text.trim_end() } else { text };
+    if !text.is_empty() {
+        segments.push(Segment::Text(text.to_string()));
+    }
+}
+
+fn count_lines(text: &str) -> usize {
+    text.matches('\n').count()
+}
+
+/// A block tag that ended a nested body
+struct EndTag {
+    keyword: String,
+    rest: String,
+    line: usize,
+}
+
+struct BlockParser {
+    segments: Vec<Segment>,
+    position: usize,
+}
+
+impl BlockParser {
+    /// Parse nodes until one of the given block keywords (or the end of input)
+    fn parse_until(&mut self, stop: &[&str]) -> Result<(Nodes, Option<EndTag>), String> {
+        let mut nodes = Vec::new();
+
+        while self.position < self.segments.len() {
+            let segment = std::mem::replace(
+                &mut self.segments[self.position],
+                Segment::Text(String::new()),
+            );
+            self.position += 1;
+
+            match segment {
+                Segment::Text(text) => nodes.push(Node::Text(text)),
+                Segment::Output(source, line) => {
+                    let expr = parse_expr(&source).map_err(|e| format!("{line}: {e}"))?;
+                    nodes.push(Node::Output { expr, line });
+                }
+                Segment::Tag(source, line) => {
+                    let (keyword, rest) = source
+                        .split_once(char::is_whitespace)
+                        .map(|(k, r)| (k.to_string(), r.trim().to_string()))
+                        .unwrap_or((source.clone(), String::new()));
+
+                    if stop.contains(&keyword.as_str()) {
+                        return Ok((
+                            nodes,
+                            Some(EndTag {
+                                keyword,
+                                rest,
+                                line,
+                            }),
+                        ));
+                    }
+
+                    let node = match keyword.as_str() {
+                        "if" => self.parse_if(&rest, line)?,
+                        "for" => self.parse_for(&rest, line)?,
+                        "include" => {
+                            parse_include(&rest, line).map_err(|e| format!("{line}: {e}"))?
+                        }
+                        "extends" if !stop.is_empty() => {
+                            return Err(format!(
+                                "{line}: {{% extends %}} must be at the top level"
+                            ));
+                        }
+                        "extends" => Node::Extends {
+                            name: parse_string_literal(&rest).ok_or_else(|| {
+                                format!("{line}: extends expects a quoted template name")
+                            })?,
+                            line,
+                        },
+                        "block" => self.parse_block(&rest, line)?,
+                        "elif" | "else" | "endif" | "endfor" | "endblock" => {
+                            return Err(format!("{line}: unexpected {{% {keyword} %}}"));
+                        }
+                        _ => return Err(format!("{line}: unknown tag '{keyword}'")),
+                    };
+                    nodes.push(node);
+                }
+            }
+        }
+
+        Ok((nodes, None))
+    }
+
+    fn parse_if(&mut self, condition: &str, line: usize) -> Result<Node, String> {
+        let mut branches = Vec::new();
+        let mut condition = parse_expr(condition).map_err(|e| format!("{line}: {e}"))?;
+
+        loop {
+            let (body, end) = self.parse_until(&["elif", "else", "endif"])?;
+            let end = end.ok_or_else(|| format!("{line}: {{% if %}} is never closed"))?;
+            branches.push((condition, body));
+
+            match end.keyword.as_str() {
+                "elif" => {
+                    condition = parse_expr(&end.rest).map_err(|e| format!("{}: {e}", end.line))?;
+                }
+                "else" => {
+                    let (otherwise, end) = self.parse_until(&["endif"])?;
+                    end.ok_or_else(|| format!("{line}: {{% if %}} is never closed"))?;
+                    return Ok(Node::If {
+                        branches,
+                        otherwise,
+                        line,
+                    });
+                }
+                _ => {
+                    return Ok(Node::If {
+                        branches,
+                        otherwise: Vec::new(),
+                        line,
+                    });
+                }
+            }
+        }
+    }
+
+    fn parse_block(&mut self, name: &str, line: usize) -> Result<Node, String> {
+        if !is_identifier(name) {
+            return Err(format!("{line}: invalid block name '{name}'"));
+        }
+        let (body, end) = self.parse_until(&["endblock"])?;
+        let end = end.ok_or_else(|| format!("{line}: {{% block {name} %}} is never closed"))?;
+        if !end.rest.is_empty() && end.rest != name {
+            return Err(format!(
+                "{}: {{% endblock {} %}} closes block '{name}'",
+                end.line, end.rest
+            ));
+        }
+
+        Ok(Node::Block {
+            name: name.to_string(),
+            body,
+            line,
+        })
+    }
+
+    fn parse_for(&mut self, header: &str, line: usize) -> Result<Node, String> {
+        let (var, iterable) = header
+            .split_once(" in ")
+            .ok_or_else(|| format!("{line}: expected {{% for item in list %}}"))?;
+        let var = var.trim();
+        if !is_identifier(var) {
+            return Err(format!("{line}: invalid loop variable '{var}'"));
+        }
+        let iterable = parse_expr(iterable).map_err(|e| format!("{line}: {e}"))?;
+
+        let (body, end) = self.parse_until(&["else", "endfor"])?;
+        let end = end.ok_or_else(|| format!("{line}: {{% for %}} is never closed"))?;
+        let empty = if end.keyword == "else" {
+            let (empty, end) = self.parse_until(&["endfor"])?;
+            end.ok_or_else(|| format!("{line}: {{% for %}} is never closed"))?;
+            empty
+        } else {
+            Vec::new()
+        };
+
+        Ok(Node::For {
+            var: var.to_string(),
+            iterable,
+            body,
+            empty,
+            line,
+        })
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parse `"name"` or `"name" with key=expr, other=expr`
+fn parse_include(source: &str, line: usize) -> Result<Node, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = ExprParser { tokens, pos: 0 };
+    let name = match parser.next() {
+        Some(Token::Str(name)) => name,
+        _ => return Err("include expects a quoted template name".to_string()),
+    };
+
+    let mut with = Vec::new();
+    if parser.eat(&Token::Ident("with".to_string())) {
+        loop {
+            let key = match parser.next() {
+                Some(Token::Ident(key)) => key,
+                other => return Err(format!("expected a variable name, found {other:?}")),
+            };
+            if !parser.eat(&Token::Assign) {
+                return Err(format!("expected '=' after '{key}'"));
+            }
+            with.push((key, parser.parse_or()?));
+            if !parser.eat(&Token::Comma) {
+                break;
+            }
+        }
+    }
+    if let Some(token) = parser.peek() {
+        return Err(format!("unexpected {token:?} in include"));
+    }
+
+    Ok(Node::Include { name, with, line })
+}
+
+fn parse_string_literal(s: &str) -> Option<String> {
+    let tokens = tokenize(s).ok()?;
+    match tokens.as_slice() {
+        [Token::Str(name)] => Some(name.clone()),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Int(i64),
+    Dot,
+    Pipe,
+    Comma,
+    LParen,
+    RParen,
+    Assign,
+    Op(BinaryOp),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some(escaped) => value.push(escaped),
+                            None => return Err("unterminated string".to_string()),
+                        },
+                        Some(ch) if ch == c => break,
+                        Some(ch) => value.push(ch),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = String::new();
+                number.push(c);
+                chars.next();
+                while let Some(&d) = chars.peek()
+                    && d.is_ascii_digit()
+                {
+                    number.push(d);
+                    chars.next();
+                }
+                let value = number
+                    .parse()
+                    .map_err(|_| format!("invalid number '{number}'"))?;
+                tokens.push(Token::Int(value));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&d) = chars.peek()
+                    && (d.is_ascii_alphanumeric() || d == '_')
+                {
+                    ident.push(d);
+                    chars.next();
+                }
+                tokens.push(match ident.as_str() {
+                    "and" => Token::Op(BinaryOp::And),
+                    "or" => Token::Op(BinaryOp::Or),
+                    "in" => Token::Op(BinaryOp::In),
+                    _ => Token::Ident(ident),
+                });
+            }
+            '.' => {
+                chars.next();
+                tokens.push(Token::Dot);
+            }
+            '|' => {
+                chars.next();
+                tokens.push(Token::Pipe);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let followed_by_eq = chars.next_if_eq(&'=').is_some();
+                if c == '=' && !followed_by_eq {
+                    tokens.push(Token::Assign);
+                    continue;
+                }
+                let op = match (c, followed_by_eq) {
+                    ('=', true) => BinaryOp::Eq,
+                    ('!', true) => BinaryOp::Ne,
+                    ('<', false) => BinaryOp::Lt,
+                    ('<', true) => BinaryOp::Le,
+                    ('>', false) => BinaryOp::Gt,
+                    ('>', true) => BinaryOp::Ge,
+                    _ => return Err(format!("unexpected '{c}'")),
+                };
+                tokens.push(Token::Op(op));
+            }
+            _ => return Err(format!("unexpected character '{c}'")),
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a complete expression
+fn parse_expr(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    let mut parser = ExprParser { tokens, pos: 0 };
+    let expr = parser.parse_or()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        return Err(format!("unexpected {token:?} in '{source}'"));
+    }
+    Ok(expr)
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_and()?;
+        while self.eat(&Token::Op(BinaryOp::Or)) {
+            let right = self.parse_and()?;
+            left = binary(BinaryOp::Or, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut left = self.parse_not()?;
+        while self.eat(&Token::Op(BinaryOp::And)) {
+            let right = self.parse_not()?;
+            left = binary(BinaryOp::And, left, right);
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Ident("not".to_string())) {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, String> {
+        let left = self.parse_filtered()?;
+        match self.peek() {
+            Some(Token::Op(op)) if !matches!(op, BinaryOp::And | BinaryOp::Or) => {
+                let op = *op;
+                self.pos += 1;
+                let right = self.parse_filtered()?;
+                Ok(binary(op, left, right))
+            }
+            _ => Ok(left),
+        }
+    }
+
+    fn parse_filtered(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_primary()?;
+        while self.eat(&Token::Pipe) {
+            let name = match self.next() {
+                Some(Token::Ident(name)) => name,
+                other => return Err(format!("expected filter name, found {other:?}")),
+            };
+            if !filters::exists(&name) {
+                return Err(format!("unknown filter '{name}'"));
+            }
+
+            let mut args = Vec::new();
+            if self.eat(&Token::LParen) && !self.eat(&Token::RParen) {
+                loop {
+                    args.push(self.parse_or()?);
+                    if self.eat(&Token::RParen) {
+                        break;
+                    }
+                    if !self.eat(&Token::Comma) {
+                        return Err(format!("expected ',' or ')' after arguments to '{name}'"));
+                    }
+                }
+            }
+
+            expr = Expr::Filter {
+                input: Box::new(expr),
+                name,
+                args,
+            };
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Str(value)) => Ok(Expr::Literal(Value::String(value))),
+            Some(Token::Int(value)) => Ok(Expr::Literal(Value::Int(value))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                if !self.eat(&Token::RParen) {
+                    return Err("expected ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "asset" | "inline_css" if matches!(self.peek(), Some(Token::Str(_))) => {
+                    match self.next() {
+                        Some(Token::Str(path)) if ident == "asset" => Ok(Expr::Asset(path)),
+                        Some(Token::Str(path)) => Ok(Expr::InlineCss(path)),
+                        _ => unreachable!("peeked a string"),
+                    }
+                }
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "none" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    let mut path = vec![ident];
+                    while self.eat(&Token::Dot) {
+                        match self.next() {
+                            Some(Token::Ident(field)) => path.push(field),
+                            Some(Token::Int(index)) if index >= 0 => path.push(index.to_string()),
+                            other => return Err(format!("expected field name, found {other:?}")),
+                        }
+                    }
+                    Ok(Expr::Path(path))
+                }
+            },
+            Some(token) => Err(format!("unexpected {token:?}")),
+            None => Err("expected an expression".to_string()),
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
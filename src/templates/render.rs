@@ -0,0 +1,289 @@
+//! Evaluates parsed templates against a context
+
+use super::filters;
+use super::parser::{self, BinaryOp, Expr, Node};
+use super::value::{Context, Value};
+use crate::escape::escape_attr;
+use std::collections::{BTreeMap, HashMap};
+
+/// Deepest include or extends chain before rendering gives up, to stop cycles
+const MAX_INCLUDE_DEPTH: usize = 32;
+
+/// Renders one top-level template, following includes into the shared template set
+pub struct Renderer<'a> {
+    templates: &'a HashMap<String, Vec<Node>>,
+    context: &'a Context,
+    /// Variables every template can see, looked up after the context
+    globals: &'a Context,
+    /// Loop and `include ... with` variables, innermost last
+    frames: Vec<BTreeMap<String, Value>>,
+    /// Templates currently being rendered, for error locations and cycle detection
+    stack: Vec<&'a str>,
+    /// Block overrides for the template being rendered, with the template defining each
+    blocks: HashMap<&'a str, (&'a str, &'a [Node])>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(
+        templates: &'a HashMap<String, Vec<Node>>,
+        context: &'a Context,
+        globals: &'a Context,
+    ) -> Self {
+        Self {
+            templates,
+            context,
+            globals,
+            frames: Vec::new(),
+            stack: Vec::new(),
+            blocks: HashMap::new(),
+        }
+    }
+
+    /// Render a template by name, following its `extends` chain up to the root template
+    pub fn render_template(&mut self, name: &'a str, out: &mut String) -> Result<(), String> {
+        if self.stack.len() >= MAX_INCLUDE_DEPTH {
+            return Err(format!(
+                "Template {name}: includes nested deeper than {MAX_INCLUDE_DEPTH}"
+            ));
+        }
+
+        // The most derived definition of each block wins
+        let mut blocks = HashMap::new();
+        let mut current = name;
+        let mut nodes = self.template(current)?;
+        let mut depth = 0;
+        while let Some(parent) = parser::parent(nodes) {
+            for (block, body) in parser::blocks(nodes) {
+                blocks.entry(block).or_insert((current, body));
+            }
+            depth += 1;
+            if depth > MAX_INCLUDE_DEPTH {
+                return Err(format!(
+                    "Template {name}: extends nested deeper than {MAX_INCLUDE_DEPTH}"
+                ));
+            }
+            current = parent;
+            nodes = self.template(current)?;
+        }
+
+        let outer_blocks = std::mem::replace(&mut self.blocks, blocks);
+        self.stack.push(current);
+        let result = self.render_nodes(nodes, out);
+        self.stack.pop();
+        self.blocks = outer_blocks;
+        result
+    }
+
+    fn template(&self, name: &str) -> Result<&'a [Node], String> {
+        self.templates
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| format!("Template '{name}' not found"))
+    }
+
+    fn render_nodes(&mut self, nodes: &'a [Node], out: &mut String) -> Result<(), String> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Output { expr, line } => {
+                    let value = self.eval(expr).map_err(|e| self.error_at(*line, e))?;
+                    write_value(&value, out).map_err(|e| self.error_at(*line, e))?;
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                    line,
+                } => {
+                    let mut taken = None;
+                    for (condition, body) in branches {
+                        let value = self.eval(condition).map_err(|e| self.error_at(*line, e))?;
+                        if value.is_truthy() {
+                            taken = Some(body);
+                            break;
+                        }
+                    }
+                    self.render_nodes(taken.unwrap_or(otherwise), out)?;
+                }
+                Node::For {
+                    var,
+                    iterable,
+                    body,
+                    empty,
+                    line,
+                } => {
+                    let items = match self.eval(iterable).map_err(|e| self.error_at(*line, e))? {
+                        Value::List(items) => items,
+                        Value::Null => Vec::new(),
+                        other => {
+                            return Err(self.error_at(
+                                *line,
+                                format!("cannot loop over a {}", other.type_name()),
+                            ));
+                        }
+                    };
+                    if items.is_empty() {
+                        self.render_nodes(empty, out)?;
+                        continue;
+                    }
+
+                    let length = items.len();
+                    for (index, item) in items.into_iter().enumerate() {
+                        let loop_info: Value = [
+                            ("index", Value::from(index + 1)),
+                            ("index0", Value::from(index)),
+                            ("first", Value::from(index == 0)),
+                            ("last", Value::from(index + 1 == length)),
+                            ("length", Value::from(length)),
+                        ]
+                        .into_iter()
+                        .collect();
+
+                        let mut frame = BTreeMap::new();
+                        frame.insert(var.clone(), item);
+                        frame.insert("loop".to_string(), loop_info);
+                        self.frames.push(frame);
+                        let result = self.render_nodes(body, out);
+                        self.frames.pop();
+                        result?;
+                    }
+                }
+                Node::Include { name, with, line } => {
+                    if !self.templates.contains_key(name.as_str()) {
+                        return Err(
+                            self.error_at(*line, format!("included template '{name}' not found"))
+                        );
+                    }
+                    let mut frame = BTreeMap::new();
+                    for (key, expr) in with {
+                        let value = self.eval(expr).map_err(|e| self.error_at(*line, e))?;
+                        frame.insert(key.clone(), value);
+                    }
+
+                    self.frames.push(frame);
+                    let result = self.render_template(name, out);
+                    self.frames.pop();
+                    result?;
+                }
+                // Only the root template's tag is reached; `render_template` resolves the chain
+                Node::Extends { .. } => {}
+                Node::Block { name, body, .. } => match self.blocks.get(name.as_str()).copied() {
+                    Some((template, body)) => {
+                        self.stack.push(template);
+                        let result = self.render_nodes(body, out);
+                        self.stack.pop();
+                        result?;
+                    }
+                    None => self.render_nodes(body, out)?,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn error_at(&self, line: usize, message: String) -> String {
+        let name = self.stack.last().copied().unwrap_or("<template>");
+        format!("Template {name}:{line}: {message}")
+    }
+
+    fn lookup(&self, path: &[String]) -> Value {
+        let Some((first, rest)) = path.split_first() else {
+            return Value::Null;
+        };
+        let root = self
+            .frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(first))
+            .or_else(|| self.context.get(first))
+            .or_else(|| self.globals.get(first));
+
+        let mut current = match root {
+            Some(value) => value,
+            None => return Value::Null,
+        };
+        for key in rest {
+            match current.get(key) {
+                Some(value) => current = value,
+                None => return Value::Null,
+            }
+        }
+        current.clone()
+    }
+
+    fn eval(&self, expr: &Expr) -> Result<Value, String> {
+        match expr {
+            Expr::Literal(value) => Ok(value.clone()),
+            Expr::Path(path) => Ok(self.lookup(path)),
+            Expr::Asset(path) => Ok(Value::String(crate::routes::get_assets().url(path))),
+            Expr::InlineCss(path) => match crate::routes::get_assets().stylesheet(path) {
+                // `</style` would end the element early; `\/` is the same character in CSS
+                Some(css) => Ok(Value::Safe(css.replace("</style", "<\\/style"))),
+                None => Err(format!("stylesheet not found: {path}")),
+            },
+            Expr::Filter { input, name, args } => {
+                let input = self.eval(input)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                filters::apply(name, input, &args)
+            }
+            Expr::Not(inner) => Ok(Value::Bool(!self.eval(inner)?.is_truthy())),
+            Expr::Binary { op, left, right } => {
+                let left = self.eval(left)?;
+                // Short-circuit like Python: `a or b` yields the first truthy operand
+                match op {
+                    BinaryOp::And if !left.is_truthy() => return Ok(left),
+                    BinaryOp::Or if left.is_truthy() => return Ok(left),
+                    BinaryOp::And | BinaryOp::Or => return self.eval(right),
+                    _ => {}
+                }
+                let right = self.eval(right)?;
+                Ok(Value::Bool(compare(*op, &left, &right)))
+            }
+        }
+    }
+}
+
+/// Compare two values; integers compare numerically, everything else as text
+fn compare(op: BinaryOp, left: &Value, right: &Value) -> bool {
+    if op == BinaryOp::In {
+        return match right {
+            Value::List(items) => items.iter().any(|item| compare(BinaryOp::Eq, left, item)),
+            Value::Map(map) => map.contains_key(&left.to_string()),
+            Value::String(s) | Value::Safe(s) => s.contains(&left.to_string()),
+            _ => false,
+        };
+    }
+
+    let ordering = match (left, right) {
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Date(a), Value::Date(b)) => a.cmp(b),
+        (Value::Null, Value::Null) => std::cmp::Ordering::Equal,
+        (Value::Null, _) | (_, Value::Null) => {
+            return matches!(op, BinaryOp::Ne);
+        }
+        _ => left.to_string().cmp(&right.to_string()),
+    };
+    match op {
+        BinaryOp::Eq => ordering.is_eq(),
+        BinaryOp::Ne => ordering.is_ne(),
+        BinaryOp::Lt => ordering.is_lt(),
+        BinaryOp::Le => ordering.is_le(),
+        BinaryOp::Gt => ordering.is_gt(),
+        BinaryOp::Ge => ordering.is_ge(),
+        BinaryOp::And | BinaryOp::Or | BinaryOp::In => unreachable!(),
+    }
+}
+
+/// Print a value, escaping it unless it is marked safe
+///
+/// Output may land in element content or an attribute, so attribute escaping is used.
+fn write_value(value: &Value, out: &mut String) -> Result<(), String> {
+    match value {
+        Value::Safe(html) => out.push_str(html),
+        Value::Map(_) => return Err("cannot print a map".to_string()),
+        other => out.push_str(&escape_attr(&other.to_string())),
+    }
+    Ok(())
+}
@@ -1,23 +1,107 @@
+use crate::config::Config;
 use log::{debug, info};
-use std::path::PathBuf;
 
+#[cfg(feature = "hot-reload")]
+use crate::blog::BlogStore;
+#[cfg(feature = "hot-reload")]
+use crate::live_reload::{self, LiveEvent};
 #[cfg(feature = "hot-reload")]
 use crate::routes;
 #[cfg(feature = "hot-reload")]
+use crate::templates::{TEMPLATE_DIR, THEMES_DIR};
+#[cfg(feature = "hot-reload")]
 use notify::{RecursiveMode, Watcher};
 #[cfg(feature = "hot-reload")]
+use std::collections::HashSet;
+#[cfg(feature = "hot-reload")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "hot-reload")]
+use std::time::Duration;
+#[cfg(feature = "hot-reload")]
 use tokio::sync::mpsc;
 
+/// Quiet period after the last file system event before reloading
+#[cfg(feature = "hot-reload")]
+const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Watched locations, resolved to absolute paths so event paths can be matched against them
+#[cfg(feature = "hot-reload")]
+struct WatchRoots {
+    content_dir: PathBuf,
+    template_dir: PathBuf,
+    themes_dir: PathBuf,
+    static_dir: PathBuf,
+    config_file: Option<PathBuf>,
+}
+
+/// Paths touched during one debounce window, split by what needs reloading
+#[cfg(feature = "hot-reload")]
+#[derive(Default)]
+struct PendingChanges {
+    content: HashSet<PathBuf>,
+    templates: bool,
+    static_files: HashSet<PathBuf>,
+    config: bool,
+}
+
+#[cfg(feature = "hot-reload")]
+impl WatchRoots {
+    fn new(config: &Config) -> Self {
+        Self {
+            content_dir: absolute(&config.content_dir),
+            template_dir: absolute(Path::new(TEMPLATE_DIR)),
+            themes_dir: absolute(Path::new(THEMES_DIR)),
+            static_dir: absolute(Path::new(&config.static_dir)),
+            config_file: config.source.as_deref().map(absolute),
+        }
+    }
+
+    /// Sort a changed path into the matching bucket, ignoring anything else
+    fn classify(&self, path: &Path, pending: &mut PendingChanges) {
+        if self.config_file.as_deref() == Some(path) {
+            pending.config = true;
+        } else if path.starts_with(&self.template_dir) || path.starts_with(&self.themes_dir) {
+            pending.templates = true;
+        } else if path.starts_with(&self.static_dir) {
+            pending.static_files.insert(path.to_path_buf());
+        } else if path.parent() == Some(self.content_dir.as_path()) {
+            pending.content.insert(path.to_path_buf());
+        }
+    }
+}
+
+/// Resolve a path to an absolute one, falling back to the path itself
+#[cfg(feature = "hot-reload")]
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
 /// Setup file watching for hot reload
-pub async fn setup_file_watcher(content_dir: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// Watches the content directory, `templates/`, `themes/`, the static directory and the
+/// config file.
+pub async fn setup_file_watcher(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(feature = "hot-reload")]
     {
         info!("Setting up file watcher for hot reload");
-        debug!("Watching directory: {content_dir:?}");
+        let roots = WatchRoots::new(config);
 
         let (tx, mut rx) = mpsc::channel(100);
         let blog_store = routes::get_blog_store();
 
+        let mut targets = vec![
+            (roots.content_dir.clone(), RecursiveMode::NonRecursive),
+            (roots.template_dir.clone(), RecursiveMode::Recursive),
+            (roots.themes_dir.clone(), RecursiveMode::Recursive),
+            (roots.static_dir.clone(), RecursiveMode::Recursive),
+        ];
+        // Watch the directory holding the config file, since editors often replace it on save
+        if let Some(parent) = roots.config_file.as_ref().and_then(|path| path.parent()) {
+            targets.push((parent.to_path_buf(), RecursiveMode::NonRecursive));
+        }
+
         // Spawn file watcher in a separate task
         tokio::spawn(async move {
             debug!("Starting file watcher task");
@@ -34,9 +118,15 @@ pub async fn setup_file_watcher(content_dir: PathBuf) -> Result<(), Box<dyn std:
                 }
             };
 
-            if let Err(e) = watcher.watch(&content_dir, RecursiveMode::NonRecursive) {
-                log::error!("Failed to watch content directory: {}", e);
-                return;
+            for (path, mode) in &targets {
+                if !path.is_dir() {
+                    log::warn!("Not watching {path:?}: directory does not exist");
+                    continue;
+                }
+                match watcher.watch(path, *mode) {
+                    Ok(()) => debug!("Watching directory: {path:?}"),
+                    Err(e) => log::error!("Failed to watch {path:?}: {e}"),
+                }
             }
 
             info!("File watcher started successfully");
@@ -47,30 +137,55 @@ pub async fn setup_file_watcher(content_dir: PathBuf) -> Result<(), Box<dyn std:
             }
         });
 
-        // Handle file change events
+        // Handle file change events, coalescing bursts into a single reload per kind
         tokio::spawn(async move {
             info!("Starting file change handler");
             while let Some(event) = rx.recv().await {
-                match event.kind {
-                    notify::EventKind::Create(_)
-                    | notify::EventKind::Modify(_)
-                    | notify::EventKind::Remove(_) => {
-                        info!("Content directory changed, reloading posts...");
-                        debug!("Event details: {:?}", event);
-                        match blog_store.load_posts().await {
-                            Ok(_) => {
-                                let post_count = blog_store.get_all_posts().len();
-                                info!("Successfully reloaded {} posts", post_count);
-                            }
-                            Err(e) => {
-                                log::error!("Failed to reload posts: {}", e);
-                            }
+                let mut pending = PendingChanges::default();
+                collect_changed_paths(&event, &roots, &mut pending);
+
+                // An editor save typically fires several events; wait for a quiet period
+                while let Ok(Some(event)) = tokio::time::timeout(DEBOUNCE_INTERVAL, rx.recv()).await
+                {
+                    collect_changed_paths(&event, &roots, &mut pending);
+                }
+
+                // The strongest browser action any change needs, sent once per batch
+                let mut live_event = None;
+
+                if pending.config {
+                    match reload_config() {
+                        Ok(theme_changed) => {
+                            pending.templates |= theme_changed;
+                            live_event = Some(LiveEvent::Reload);
                         }
+                        Err(e) => log::error!("Config reload failed, keeping current config: {e}"),
                     }
-                    _ => {
-                        debug!("Ignoring event: {:?}", event.kind);
+                }
+                if pending.templates && reload_templates() {
+                    live_event = Some(LiveEvent::Reload);
+                }
+                let inlined_before = inlined_stylesheets();
+                if !pending.static_files.is_empty() && reload_assets(&pending.static_files) {
+                    let css_only = pending
+                        .static_files
+                        .iter()
+                        .all(|path| path.extension().is_some_and(|ext| ext == "css"));
+                    // Swapping `<link>` tags misses stylesheets inlined into `<style>`
+                    if !css_only || inlined_stylesheets() != inlined_before {
+                        live_event = Some(LiveEvent::Reload);
+                    } else if live_event.is_none() {
+                        live_event = Some(LiveEvent::Css);
                     }
                 }
+                if !pending.content.is_empty() && reload_content(blog_store, pending.content).await
+                {
+                    live_event = Some(LiveEvent::Reload);
+                }
+
+                if let Some(event) = live_event {
+                    live_reload::notify(event);
+                }
             }
         });
     }
@@ -78,8 +193,131 @@ pub async fn setup_file_watcher(content_dir: PathBuf) -> Result<(), Box<dyn std:
     #[cfg(not(feature = "hot-reload"))]
     {
         info!("Hot reload disabled - file watching not available");
-        debug!("Content directory: {content_dir:?}");
+        debug!("Content directory: {:?}", config.content_dir);
     }
 
     Ok(())
 }
+
+/// Record the paths touched by a create, modify or remove event
+#[cfg(feature = "hot-reload")]
+fn collect_changed_paths(event: &notify::Event, roots: &WatchRoots, pending: &mut PendingChanges) {
+    match event.kind {
+        notify::EventKind::Create(_)
+        | notify::EventKind::Modify(_)
+        | notify::EventKind::Remove(_) => {
+            debug!("Event details: {:?}", event);
+            for path in &event.paths {
+                roots.classify(path, pending);
+            }
+        }
+        _ => {
+            debug!("Ignoring event: {:?}", event.kind);
+        }
+    }
+}
+
+/// Reload the changed posts, returning whether anything visible changed
+#[cfg(feature = "hot-reload")]
+async fn reload_content(blog_store: &BlogStore, changed: HashSet<PathBuf>) -> bool {
+    let mut paths: Vec<PathBuf> = changed.into_iter().collect();
+    paths.sort();
+    info!("Content changed, reloading {} file(s)...", paths.len());
+    debug!("Changed paths: {paths:?}");
+
+    let diff = blog_store.reload_paths(&paths).await;
+    if !diff.failed.is_empty() {
+        log::warn!(
+            "Posts failed to parse, keeping any previous version online: {}",
+            diff.failed.join(", ")
+        );
+    }
+    // Cards of changed posts are re-rendered on their next request if their content changed
+    routes::get_card_cache().remove(&diff.removed);
+    if diff.has_changes() {
+        info!(
+            "Reloaded posts ({diff}), now serving {}",
+            blog_store.post_count()
+        );
+    } else {
+        debug!("Reload produced no changes");
+    }
+    diff.has_changes()
+}
+
+/// Rebuild the template engine, keeping the last good one if any template is broken
+///
+/// Returns whether the new templates were swapped in.
+#[cfg(feature = "hot-reload")]
+fn reload_templates() -> bool {
+    info!("Templates changed, reloading...");
+    match routes::reload_template_engine() {
+        Ok(()) => {
+            info!("Templates reloaded");
+            true
+        }
+        Err(e) => {
+            log::error!("Template reload failed, keeping last good templates: {e}");
+            false
+        }
+    }
+}
+
+/// Rebuild fingerprints and stylesheet bundles, keeping the old ones if that fails
+///
+/// Other static files are served from disk as they are. Returns whether the new assets
+/// were swapped in.
+#[cfg(feature = "hot-reload")]
+fn reload_assets(changed: &HashSet<PathBuf>) -> bool {
+    info!("Static files changed: {} file(s)", changed.len());
+    debug!("Changed static files: {changed:?}");
+    match routes::reload_assets() {
+        Ok(()) => true,
+        Err(e) => {
+            log::error!("Failed to fingerprint static assets, keeping the last good ones: {e}");
+            false
+        }
+    }
+}
+
+/// Bundles of the stylesheets the templates inline, to tell whether a change touched them
+#[cfg(feature = "hot-reload")]
+fn inlined_stylesheets() -> Vec<Option<std::sync::Arc<str>>> {
+    let assets = routes::get_assets();
+    routes::get_template_engine()
+        .inlined_stylesheets()
+        .iter()
+        .map(|path| assets.stylesheet(path))
+        .collect()
+}
+
+/// Re-read the config file and swap it in
+///
+/// Settings that are bound at startup (listen address, directories) only take effect
+/// after a restart. Returns whether the theme changed, so the templates need rebuilding;
+/// if the file can't be loaded, the current config stays and the error is returned.
+#[cfg(feature = "hot-reload")]
+fn reload_config() -> Result<bool, String> {
+    info!("Config changed, reloading...");
+    let old = routes::get_config();
+    let new = Config::load(old.overrides.clone())?;
+
+    if new.host != old.host || new.port != old.port {
+        log::warn!("Listen address changed, restart the server to apply it");
+    }
+    if new.static_dir != old.static_dir || new.content_dir != old.content_dir {
+        log::warn!("Directory settings changed, restart the server to apply them");
+    }
+    if new.mode != old.mode || new.drafts != old.drafts {
+        log::warn!("Mode or draft settings changed, restart the server to apply them");
+    }
+    if new.allowed_html != old.allowed_html {
+        log::warn!("HTML allowlist changed, restart the server to re-render posts with it");
+    }
+
+    let theme_changed = new.theme != old.theme;
+
+    routes::set_config(new);
+    info!("Config reloaded");
+    Ok(theme_changed)
+}
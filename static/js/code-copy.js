@@ -0,0 +1,34 @@
+// Copy buttons for code blocks
+//
+// The buttons are rendered hidden with every code block; this script shows them when
+// the clipboard is available. `textContent` leaves out the line numbers, which are
+// drawn by CSS; console prompts and output are removed so only commands are copied.
+function copyableText(code) {
+    const copy = code.cloneNode(true);
+    copy.querySelectorAll('.prompt').forEach((prompt) => prompt.remove());
+    copy.querySelectorAll('.output').forEach((output) => {
+        (output.closest('.line') || output).remove();
+    });
+    return copy.textContent;
+}
+
+document.querySelectorAll('.code-copy-btn').forEach((button) => {
+    if (!navigator.clipboard) {
+        return;
+    }
+    button.hidden = false;
+    button.addEventListener('click', async () => {
+        const code = button.closest('pre').querySelector('code');
+        try {
+            await navigator.clipboard.writeText(copyableText(code));
+            button.textContent = 'Copied';
+            button.classList.add('copied');
+        } catch {
+            button.textContent = 'Failed';
+        }
+        setTimeout(() => {
+            button.textContent = 'Copy';
+            button.classList.remove('copied');
+        }, 2000);
+    });
+});